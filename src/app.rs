@@ -1,14 +1,20 @@
 // src/app.rs
 use crate::data;
 use crate::llm::LlmClient;
+use crate::llm_provider::Model;
+use crate::model_picker::ModelPicker;
+use crate::usage::UsageSnapshot;
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use std::fmt;
+use std::path::{Path, PathBuf};
 
 // Define LearningGoal enum for personalized learning paths
 #[derive(PartialEq, Clone, Copy, Serialize, Deserialize, Debug, )]
+#[allow(clippy::upper_case_acronyms)] // Variant names are persisted in the config file
 pub enum LearningGoal {
     ARVR, // Augmented Reality / Virtual Reality
     AsyncProgramming,
@@ -109,8 +115,14 @@ impl fmt::Display for LearningGoal {
 }
 
 
-use crate::prompt_response::{CodeSnippet, Exercise};
+use crate::prompt_response::{CodeSnippet, Diagnostic, Exercise};
+use crate::syntax_check;
 use crate::cargo_project;
+use crate::dependencies::CrateResolver;
+use crate::compile_check::{self, CompileReport, CompilerDiagnostic};
+use crate::snippet_runner::{self, RunEvent};
+use crate::grader::{self, GradeReport};
+use crate::watch::WatchSession;
 use crate::config::ConfigService;
 
 #[derive(Clone)]
@@ -120,6 +132,16 @@ pub struct LearningModule {
     pub code_snippets: Vec<CodeSnippet>,
     pub exercises: Vec<Exercise>,
     pub additional_resources: Option<AdditionalResources>,
+    pub model: Option<String>, // Model that produced the module, None for error placeholders
+    pub diagnostics: Vec<Diagnostic>, // Parser warnings about content that was lost or misplaced
+}
+
+// Messages sent from the module generation task to the main app
+pub enum ModuleUpdate {
+    // Partially parsed module while the response is still streaming
+    Partial(LearningModule),
+    // Final result of the generation
+    Complete(Result<LearningModule>),
 }
 
 #[derive(Clone)]
@@ -137,6 +159,29 @@ pub struct Resource {
     pub description: String,
 }
 
+// Output of a snippet run from the learning view, shown below the snippet's code
+pub struct SnippetRun {
+    pub snippet_index: usize,
+    // Output lines followed by the outcome once the run is over
+    pub events: Vec<RunEvent>,
+}
+
+// Result of (t) Check solution for one exercise; `report` is None while the tests run
+pub struct ExerciseCheck {
+    pub exercise_index: usize,
+    pub report: Option<GradeReport>,
+}
+
+// The code a snippet repair rewrites, by index into the current module
+#[derive(Debug, Clone, Copy)]
+enum RepairTarget {
+    Snippet(usize),
+    Exercise(usize),
+}
+
+// Runaway loops printing forever should not grow the pane without bound
+const MAX_RUN_OUTPUT_LINES: usize = 500;
+
 #[derive(PartialEq)]
 pub enum AppState {
     Welcome,
@@ -149,6 +194,7 @@ pub enum AppState {
     QuestionAnswering,
     ApplicationGeneration,
     ApplicationDisplay,
+    Watch,
 }
 
 pub enum IndexType {
@@ -169,13 +215,17 @@ pub struct App {
     pub show_help: bool,
     pub show_quit_confirmation: bool,
     pub quit_confirmation_selected: bool, // true = Yes, false = No
-    pub api_key: String,
     pub scroll_offset: u16,
     pub current_module: Option<LearningModule>,
+    pub current_project_dir: Option<PathBuf>, // Cargo project created for the current module
+    // Loaded with the project and reused for its repairs, since loading scans the whole registry cache
+    crate_resolver: Option<CrateResolver>,
     pub popup_start_time: Option<std::time::Instant>, // For tracking popup display time
+    pub module_streaming: bool, // True while a streamed module is still arriving
     llm_client: LlmClient,
-    module_receiver: mpsc::Receiver<Result<LearningModule>>,
-    module_sender: mpsc::Sender<Result<LearningModule>>,
+    // Each generation gets a fresh channel, so results of a cancelled or superseded task are dropped
+    module_receiver: Option<mpsc::Receiver<ModuleUpdate>>,
+    module_task: Option<JoinHandle<()>>,
     config_service: ConfigService,
     // Question generator fields
     pub question_set: Option<crate::question_generator::QuestionSet>,
@@ -183,6 +233,39 @@ pub struct App {
     pub question_generator: Option<crate::question_generator::QuestionGenerator>,
     pub question_generation_rx: Option<mpsc::UnboundedReceiver<Result<crate::question_generator::QuestionSet>>>,
     pub application_generation_rx: Option<mpsc::UnboundedReceiver<Result<crate::question_generator::GeneratedApplication>>>,
+    question_task: Option<JoinHandle<()>>,
+    application_task: Option<JoinHandle<()>>,
+    // Regenerated code for snippets or exercises of the current module that failed to parse or build
+    snippet_repair_rx: Option<mpsc::UnboundedReceiver<(RepairTarget, Result<String>)>>,
+    snippet_repair_task: Option<JoinHandle<()>>,
+    // Message for the commit of the rewritten files once the repair finishes
+    snippet_repair_message: String,
+    // Files rewritten so far by the repair, relative to the project directory
+    snippet_repair_paths: Vec<PathBuf>,
+    // `cargo check` of the current module's project; the report is replaced whenever its files change
+    pub compile_report: Option<CompileReport>,
+    // Rounds of compiler errors sent back to the model for the current module, limited by `compile_fix_attempts`
+    pub compile_fix_round: u32,
+    // Snippet targeted by (x) Run, cycled with Tab
+    pub selected_snippet: usize,
+    pub snippet_run: Option<SnippetRun>,
+    snippet_run_rx: Option<mpsc::UnboundedReceiver<RunEvent>>,
+    snippet_run_task: Option<JoinHandle<()>>,
+    // Exercise targeted by (t) Check solution, cycled with 'e'
+    pub selected_exercise: usize,
+    pub exercise_check: Option<ExerciseCheck>,
+    exercise_check_rx: Option<mpsc::UnboundedReceiver<Result<GradeReport>>>,
+    exercise_check_task: Option<JoinHandle<()>>,
+    // Watch mode over the exercises of the current module's project
+    pub watch_session: Option<WatchSession>,
+    pub watch_scroll: u16,
+    watch_check_rx: Option<mpsc::UnboundedReceiver<GradeReport>>,
+    watch_check_task: Option<JoinHandle<()>>,
+    compile_check_rx: Option<mpsc::UnboundedReceiver<Result<CompileReport>>>,
+    compile_check_task: Option<JoinHandle<()>>,
+    // Model picker fields
+    pub model_picker: ModelPicker,
+    model_list_rx: Option<mpsc::UnboundedReceiver<Result<Vec<Model>>>>,
 }
 
 #[derive(PartialEq)]
@@ -191,18 +274,17 @@ pub enum SettingsSection {
     ContentCustomization,
     LearningGoals,
     QuestionGenerator,
+    Model,
+    Usage,
 }
 
 impl App {
-    pub fn new(api_key: String) -> Self {
-        // Create a channel for communicating between the LLM task and the main app
-        let (module_sender, module_receiver) = mpsc::channel(10);
-
-        // Initialize config service
-        let config_service = ConfigService::new();
-
-        let llm_client = LlmClient::new(api_key.clone());
+    pub fn new(api_key: String, config_service: ConfigService) -> Self {
+        let llm_client = LlmClient::new(api_key, config_service.get_config().clone());
+        Self::with_llm_client(llm_client, config_service)
+    }
 
+    pub fn with_llm_client(llm_client: LlmClient, config_service: ConfigService) -> Self {
         Self {
             is_running: true,
             current_state:  AppState::Welcome,
@@ -215,12 +297,14 @@ impl App {
             show_quit_confirmation: false,
             quit_confirmation_selected: false, // Default to "No"
             llm_client: llm_client.clone(),
-            api_key,
             scroll_offset: 0,
             current_module: None,
+            current_project_dir: None,
+            crate_resolver: None,
             popup_start_time: None,
-            module_receiver,
-            module_sender,
+            module_streaming: false,
+            module_receiver: None,
+            module_task: None,
             config_service,
             // Initialize question generator fields
             question_set: None,
@@ -228,158 +312,778 @@ impl App {
             question_generator: Some(crate::question_generator::QuestionGenerator::new(llm_client)),
             question_generation_rx: None,
             application_generation_rx: None,
+            question_task: None,
+            application_task: None,
+            snippet_repair_rx: None,
+            snippet_repair_task: None,
+            compile_report: None,
+            snippet_repair_message: String::new(),
+            snippet_repair_paths: Vec::new(),
+            compile_fix_round: 0,
+            selected_snippet: 0,
+            snippet_run: None,
+            snippet_run_rx: None,
+            snippet_run_task: None,
+            selected_exercise: 0,
+            exercise_check: None,
+            exercise_check_rx: None,
+            exercise_check_task: None,
+            watch_session: None,
+            watch_scroll: 0,
+            watch_check_rx: None,
+            watch_check_task: None,
+            compile_check_rx: None,
+            compile_check_task: None,
+            model_picker: ModelPicker::default(),
+            model_list_rx: None,
         }
     }
 
+    // Retry progress of the LLM call in flight, shown on the loading screens
+    pub fn llm_retry_status(&self) -> Option<String> {
+        self.llm_client.retry_status()
+    }
+
+    // Crates the current module's code uses that have no cached copy, so they could not be added to its project
+    pub fn missing_crates(&self) -> Vec<String> {
+        self.crate_resolver.as_ref().map(CrateResolver::missing).unwrap_or_default()
+    }
+
     pub fn tick(&mut self) {
         // Check if we're in the LevelTooLowPopup state and if the timer has expired
-        if let AppState::LevelTooLowPopup = self.current_state {
-            if let Some(start_time) = self.popup_start_time {
-                // Check if 3 seconds have passed
-                if start_time.elapsed().as_secs() >= 3 {
-                    // Reset the timer
-                    self.popup_start_time = None;
-                    // Return to the welcome screen
-                    self.current_state = AppState::Welcome;
+        if let AppState::LevelTooLowPopup = self.current_state
+            && let Some(start_time) = self.popup_start_time
+            // Check if 3 seconds have passed
+            && start_time.elapsed().as_secs() >= 3
+        {
+            // Reset the timer
+            self.popup_start_time = None;
+            // Return to the welcome screen
+            self.current_state = AppState::Welcome;
+        }
+
+        // Poll for the model list requested by the model picker
+        if let Some(ref mut rx) = self.model_list_rx {
+            match rx.try_recv() {
+                Ok(Ok(models)) => {
+                    let current = self.config_service.get_config().model.clone();
+                    self.model_picker.set_models(models, &current);
+                    self.model_list_rx = None;
+                }
+                Ok(Err(err)) => {
+                    tracing::error!("Failed to list models: {}", err);
+                    self.model_picker.loading = false;
+                    self.model_picker.error = Some(err.to_string());
+                    self.model_list_rx = None;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => {}
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.model_picker.loading = false;
+                    self.model_list_rx = None;
                 }
             }
         }
 
         // Check if we're in the QuestionGeneration state and poll for results
-        if let AppState::QuestionGeneration = self.current_state {
-            if let Some(ref mut rx) = self.question_generation_rx {
-                match rx.try_recv() {
-                    Ok(result) => {
-                        match result {
-                            Ok(question_set) => {
-                                self.question_set = Some(question_set);
-                                self.current_state = AppState::QuestionAnswering;
-                            },
-                            Err(err) => {
-                                tracing::error!("Failed to generate questions: {}", err);
-                                self.current_state = AppState::Learning;
-                            }
+        if let AppState::QuestionGeneration = self.current_state
+            && let Some(ref mut rx) = self.question_generation_rx
+        {
+            match rx.try_recv() {
+                Ok(result) => {
+                    match result {
+                        Ok(question_set) => {
+                            self.question_set = Some(question_set);
+                            self.current_state = AppState::QuestionAnswering;
+                        },
+                        Err(err) => {
+                            tracing::error!("Failed to generate questions: {}", err);
+                            self.current_state = AppState::Learning;
                         }
-                        self.question_generation_rx = None;
-                    },
-                    Err(mpsc::error::TryRecvError::Empty) => {
-                        // Still waiting for the result
-                    },
-                    Err(mpsc::error::TryRecvError::Disconnected) => {
-                        tracing::error!("Question generation task disconnected");
-                        self.current_state = AppState::Learning;
-                        self.question_generation_rx = None;
                     }
+                    self.question_generation_rx = None;
+                    self.question_task = None;
+                },
+                Err(mpsc::error::TryRecvError::Empty) => {
+                    // Still waiting for the result
+                },
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    tracing::error!("Question generation task disconnected");
+                    self.current_state = AppState::Learning;
+                    self.question_generation_rx = None;
+                    self.question_task = None;
                 }
             }
         }
 
         // Check if we're in the ApplicationGeneration state and poll for results
-        if let AppState::ApplicationGeneration = self.current_state {
-            if let Some(ref mut rx) = self.application_generation_rx {
-                match rx.try_recv() {
-                    Ok(result) => {
-                        match result {
-                            Ok(application) => {
-                                self.generated_application = Some(application);
-                                self.current_state = AppState::ApplicationDisplay;
+        if let AppState::ApplicationGeneration = self.current_state
+            && let Some(ref mut rx) = self.application_generation_rx
+        {
+            match rx.try_recv() {
+                Ok(result) => {
+                    match result {
+                        Ok(application) => {
+                            self.generated_application = Some(application);
+                            self.current_state = AppState::ApplicationDisplay;
 
-                                // Transition to loading state
-                                //self.current_state = AppState::Loading;
+                            // Transition to loading state
+                            //self.current_state = AppState::Loading;
 
-                                // Generate a learning module based on the selected level and index
-                                self.generate_learning_module();
+                            // Generate a learning module based on the selected level and index
+                            self.generate_learning_module();
 
-                            },
-                            Err(err) => {
-                                tracing::error!("Failed to generate application: {}", err);
-                                self.current_state = AppState::QuestionAnswering;
-                            }
+                        },
+                        Err(err) => {
+                            tracing::error!("Failed to generate application: {}", err);
+                            self.current_state = AppState::QuestionAnswering;
                         }
-                        self.application_generation_rx = None;
-                    },
-                    Err(mpsc::error::TryRecvError::Empty) => {
-                        // Still waiting for the result
-                    },
-                    Err(mpsc::error::TryRecvError::Disconnected) => {
-                        tracing::error!("Application generation task disconnected");
-                        self.current_state = AppState::QuestionAnswering;
-                        self.application_generation_rx = None;
                     }
+                    self.application_generation_rx = None;
+                    self.application_task = None;
+                },
+                Err(mpsc::error::TryRecvError::Empty) => {
+                    // Still waiting for the result
+                },
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    tracing::error!("Application generation task disconnected");
+                    self.current_state = AppState::QuestionAnswering;
+                    self.application_generation_rx = None;
+                    self.application_task = None;
                 }
             }
         }
 
-        // Check if we're in the Loading state and if there's a message from the LLM client
-        if let AppState::Loading = self.current_state {
-            // Try to receive a message from the channel (non-blocking)
-            match self.module_receiver.try_recv() {
-                Ok(result) => {
-                    match result {
-                        Ok(module) => {
-                            // Generate additional resources if enabled
-                            let mut module_with_resources = module.clone();
-                            module_with_resources.additional_resources = self.generate_additional_resources(&module.topic);
+        self.poll_snippet_repairs();
+        self.poll_compile_check();
+        self.poll_snippet_run();
+        self.poll_exercise_check();
+        if self.current_state == AppState::Watch {
+            self.poll_watch();
+        }
 
-                            // Update the state
-                            self.current_module = Some(module_with_resources);
+        // Check if a learning module is being generated and if there are messages from the LLM client
+        if (self.current_state == AppState::Loading || self.module_streaming)
+            && let Some(ref mut rx) = self.module_receiver
+        {
+            // Drain every pending message (non-blocking) so streamed text keeps up with the model
+            loop {
+                match rx.try_recv() {
+                    Ok(ModuleUpdate::Partial(module)) => {
+                        // Switch to the learning view as soon as the first text arrives
+                        if self.current_state == AppState::Loading {
                             self.current_state = AppState::Learning;
                             self.scroll_offset = 0; // Reset scroll position for new content
+                        }
+                        self.module_streaming = true;
+                        self.current_module = Some(module);
+                    }
+                    Ok(ModuleUpdate::Complete(result)) => {
+                        self.module_streaming = false;
+                        self.module_receiver = None;
+                        self.module_task = None;
+                        self.finish_learning_module(result);
+                        break;
+                    }
+                    Err(mpsc::error::TryRecvError::Empty) => {
+                        // No message yet, continue waiting
+                        break;
+                    }
+                    Err(mpsc::error::TryRecvError::Disconnected) => {
+                        // Channel is disconnected, this shouldn't happen in normal operation
+                        tracing::error!("Module channel disconnected");
+                        self.module_streaming = false;
+                        self.module_receiver = None;
+                        self.module_task = None;
+
+                        // Create an error module
+                        let error_module = LearningModule {
+                            topic: "Communication Error".to_string(),
+                            explanation: "There was an error communicating with the content generation service.\n\nPlease try again or select a different level.".to_string(),
+                            code_snippets: vec![],
+                            exercises: vec![],
+                            additional_resources: None,
+                            model: None,
+                            diagnostics: Vec::new(),
+                        };
+
+                        self.current_module = Some(error_module);
+                        self.current_state = AppState::Learning;
+                        break;
+                    }
+                }
+            }
+        }
+    }
 
-                            // Create a Cargo project for the learning module
-                            match cargo_project::create_cargo_project(&module, self.selected_level) {
-                                Ok(project_dir) => {
-                                    tracing::info!("Created Cargo project at: {:?}", project_dir);
-                                }
-                                Err(err) => {
-                                    tracing::error!("Failed to create Cargo project: {}", err);
-                                }
+    // Apply the final result of a learning module generation
+    fn finish_learning_module(&mut self, result: Result<LearningModule>) {
+        // Streamed modules are already on screen; only leave the loading view if we are still in it
+        if self.current_state == AppState::Loading {
+            self.current_state = AppState::Learning;
+            self.scroll_offset = 0; // Reset scroll position for new content
+        }
+
+        match result {
+            Ok(module) => {
+                // Generate additional resources if enabled
+                let mut module_with_resources = module.clone();
+                module_with_resources.additional_resources = self.generate_additional_resources(&module.topic);
+
+                // Update the state
+                self.current_module = Some(module_with_resources);
+
+                // Create a Cargo project for the learning module
+                let resolver = CrateResolver::load();
+                match cargo_project::create_cargo_project(&module, self.selected_level, &self.config_service.get_config().projects, &resolver) {
+                    Ok(project_dir) => {
+                        tracing::info!("Created Cargo project at: {:?}", project_dir);
+                        self.current_project_dir = Some(project_dir);
+                        self.crate_resolver = Some(resolver);
+                        self.start_compile_check();
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to create Cargo project: {}", err);
+                    }
+                }
+            }
+            Err(err) => {
+                // There was an error generating the module
+                tracing::error!("Failed to generate learning module: {}", err);
+
+                // Create an error module
+                let error_module = LearningModule {
+                    topic: "Error Generating Content".to_string(),
+                    explanation: format!(
+                        "There was an error generating content: {}\n\nPlease try again or select a different level.",
+                        err
+                    ),
+                    code_snippets: vec![],
+                    exercises: vec![],
+                    additional_resources: None,
+                    model: None,
+                    diagnostics: Vec::new(),
+                };
+
+                self.current_module = Some(error_module);
+            }
+        }
+    }
+
+    // True while broken snippets of the current module are being regenerated
+    pub fn is_repairing_snippets(&self) -> bool {
+        self.snippet_repair_rx.is_some()
+    }
+
+    // Ask the model to rewrite the selected snippet if it does not parse
+    fn regenerate_selected_snippet(&mut self) {
+        let Some(module) = &self.current_module else {
+            return;
+        };
+        let index = self.selected_snippet;
+        let Some(snippet) = module.code_snippets.get(index).cloned() else {
+            return;
+        };
+        let Some(error) = snippet.syntax_error.clone() else {
+            return;
+        };
+        if self.is_repairing_snippets() {
+            return;
+        }
+
+        let topic = module.topic.clone();
+        let llm_client = self.llm_client.clone();
+        let message = format!("Regenerate code snippet \"{}\" that was not valid Rust", snippet.title);
+        self.start_snippet_repair(message, async move {
+            (RepairTarget::Snippet(index), llm_client.regenerate_snippet(&topic, &snippet, &error).await)
+        });
+    }
+
+    // Ask the model to rewrite the starter code of the selected exercise if it does not parse
+    fn regenerate_selected_exercise(&mut self) {
+        let Some(module) = &self.current_module else {
+            return;
+        };
+        let index = self.selected_exercise;
+        let Some(exercise) = module.exercises.get(index).cloned() else {
+            return;
+        };
+        let Some(error) = exercise.syntax_error.clone() else {
+            return;
+        };
+        if self.is_repairing_snippets() {
+            return;
+        }
+
+        let topic = module.topic.clone();
+        let llm_client = self.llm_client.clone();
+        let message = format!("Regenerate starter code of exercise \"{}\" that was not valid Rust", exercise.name);
+        self.start_snippet_repair(message, async move {
+            (RepairTarget::Exercise(index), llm_client.regenerate_exercise(&topic, &exercise, &error).await)
+        });
+    }
+
+    fn start_snippet_repair(
+        &mut self,
+        message: String,
+        repair: impl std::future::Future<Output = (RepairTarget, Result<String>)> + Send + 'static,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.snippet_repair_task = Some(tokio::spawn(async move {
+            let _ = tx.send(repair.await);
+        }));
+        self.snippet_repair_rx = Some(rx);
+        self.snippet_repair_message = message;
+        self.snippet_repair_paths.clear();
+    }
+
+    fn poll_snippet_repairs(&mut self) {
+        let Some(rx) = &mut self.snippet_repair_rx else {
+            return;
+        };
+        let mut updated = Vec::new();
+        let mut finished = false;
+        loop {
+            match rx.try_recv() {
+                Ok((target, Ok(code))) => {
+                    let Some(module) = self.current_module.as_mut() else {
+                        continue;
+                    };
+                    match target {
+                        RepairTarget::Snippet(index) => {
+                            let Some(snippet) = module.code_snippets.get_mut(index) else {
+                                continue;
+                            };
+                            snippet.syntax_error = syntax_check::check(&code).err();
+                            match &snippet.syntax_error {
+                                Some(err) => tracing::warn!("Regenerated snippet \"{}\" still does not parse: {}", snippet.title, err),
+                                None => tracing::info!("Regenerated snippet \"{}\"", snippet.title),
                             }
+                            snippet.code = code;
                         }
-                        Err(err) => {
-                            // There was an error generating the module
-                            tracing::error!("Failed to generate learning module: {}", err);
-
-                            // Create an error module
-                            let error_module = LearningModule {
-                                topic: "Error Generating Content".to_string(),
-                                explanation: format!(
-                                    "There was an error generating content: {}\n\nPlease try again or select a different level.",
-                                    err
-                                ),
-                                code_snippets: vec![],
-                                exercises: vec![],
-                                additional_resources: None,
+                        RepairTarget::Exercise(index) => {
+                            let Some(exercise) = module.exercises.get_mut(index) else {
+                                continue;
                             };
-
-                            self.current_module = Some(error_module);
-                            self.current_state = AppState::Learning;
+                            exercise.syntax_error = syntax_check::check(&code).err();
+                            match &exercise.syntax_error {
+                                Some(err) => tracing::warn!("Regenerated exercise \"{}\" still does not parse: {}", exercise.name, err),
+                                None => tracing::info!("Regenerated exercise \"{}\"", exercise.name),
+                            }
+                            exercise.starter_code = code;
                         }
                     }
+                    updated.push(target);
                 }
-                Err(mpsc::error::TryRecvError::Empty) => {
-                    // No message yet, continue waiting
+                Ok((target, Err(err))) => {
+                    tracing::error!("Failed to regenerate {:?}: {}", target, err);
                 }
+                Err(mpsc::error::TryRecvError::Empty) => break,
                 Err(mpsc::error::TryRecvError::Disconnected) => {
-                    // Channel is disconnected, this shouldn't happen in normal operation
-                    tracing::error!("Module channel disconnected");
-
-                    // Create an error module
-                    let error_module = LearningModule {
-                        topic: "Communication Error".to_string(),
-                        explanation: "There was an error communicating with the content generation service.\n\nPlease try again or select a different level.".to_string(),
-                        code_snippets: vec![],
-                        exercises: vec![],
-                        additional_resources: None,
-                    };
+                    self.snippet_repair_rx = None;
+                    self.snippet_repair_task = None;
+                    finished = true;
+                    break;
+                }
+            }
+        }
 
-                    self.current_module = Some(error_module);
-                    self.current_state = AppState::Learning;
+        // Keep the files of the module's Cargo project in sync; only the rewritten snippets and exercises
+        // are replaced, the learner may have edited the others
+        if let (Some(project_dir), Some(module)) = (&self.current_project_dir, &self.current_module) {
+            let snippet_paths = cargo_project::code_snippet_paths(&module.code_snippets);
+            let exercise_paths = cargo_project::exercise_paths(&module.exercises);
+            let resolver = self.crate_resolver.get_or_insert_with(CrateResolver::load);
+            for target in updated {
+                let (path, result) = match target {
+                    RepairTarget::Snippet(index) => (
+                        snippet_paths[index].clone(),
+                        cargo_project::update_code_snippet_file(project_dir, module, index, resolver),
+                    ),
+                    RepairTarget::Exercise(index) => (
+                        exercise_paths[index].clone(),
+                        cargo_project::update_exercise_file(project_dir, module, index, resolver),
+                    ),
+                };
+                match result {
+                    Ok(()) => self.snippet_repair_paths.push(path),
+                    Err(err) => tracing::error!("Failed to update {}: {}", path.display(), err),
+                }
+            }
+        }
+        // Record the rewritten files, then build the project again once every requested repair is back.
+        // git runs on the blocking pool so the UI keeps drawing meanwhile.
+        if finished {
+            if let Some(project_dir) = &self.current_project_dir
+                && !self.snippet_repair_paths.is_empty()
+            {
+                let mut paths = std::mem::take(&mut self.snippet_repair_paths);
+                paths.push(PathBuf::from("Cargo.toml"));
+                let project_dir = project_dir.clone();
+                let message = self.snippet_repair_message.clone();
+                let settings = self.config_service.get_config().projects.clone();
+                tokio::task::spawn_blocking(move || {
+                    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+                    cargo_project::commit_project_files(&project_dir, &paths, &message, &settings);
+                });
+            }
+            self.start_compile_check();
+        }
+    }
+
+    // True while `cargo check` runs on the current module's project
+    pub fn is_compile_checking(&self) -> bool {
+        self.compile_check_rx.is_some()
+    }
+
+    // Compile the current module's project in the background; a check already running is superseded
+    fn start_compile_check(&mut self) {
+        let Some(project_dir) = self.current_project_dir.clone() else {
+            return;
+        };
+        self.cancel_compile_check();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.compile_check_task = Some(tokio::spawn(async move {
+            let _ = tx.send(compile_check::check_project(&project_dir).await);
+        }));
+        self.compile_check_rx = Some(rx);
+    }
+
+    fn poll_compile_check(&mut self) {
+        let Some(rx) = &mut self.compile_check_rx else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::error::TryRecvError::Empty) => return,
+            Err(mpsc::error::TryRecvError::Disconnected) => Err(anyhow::anyhow!("Compile check task disconnected")),
+        };
+        self.compile_check_rx = None;
+        self.compile_check_task = None;
+
+        self.compile_report = Some(match result {
+            Ok(report) => {
+                let passed = report.files.iter().filter(|file| file.passed()).count();
+                tracing::info!("Compile check finished: {} of {} files compile", passed, report.files.len());
+                report
+            }
+            Err(err) => {
+                tracing::error!("Failed to compile check project: {}", err);
+                CompileReport {
+                    files: Vec::new(),
+                    project_errors: vec![err.to_string()],
+                }
+            }
+        });
+        self.fix_failing_snippets();
+    }
+
+    // Send the compiler errors of snippets that do not build back to the model, one round per compile check
+    fn fix_failing_snippets(&mut self) {
+        let (Some(module), Some(report)) = (&self.current_module, &self.compile_report) else {
+            return;
+        };
+        // Exercises are left alone: their starter code may not compile on purpose
+        let paths = cargo_project::code_snippet_paths(&module.code_snippets);
+        let failing: Vec<(usize, CodeSnippet, Vec<CompilerDiagnostic>)> = module
+            .code_snippets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, snippet)| {
+                let file = report.file(&paths[index])?;
+                file.failed().then(|| (index, snippet.clone(), file.errors.clone()))
+            })
+            .collect();
+        if failing.is_empty() || self.is_repairing_snippets() {
+            return;
+        }
+        if self.compile_fix_round >= self.config_service.get_config().compile_fix_attempts {
+            tracing::warn!("{} snippet(s) still do not compile after {} fix round(s)", failing.len(), self.compile_fix_round);
+            return;
+        }
+        self.compile_fix_round += 1;
+        tracing::info!("Asking the model to fix {} snippet(s) that do not compile (round {})", failing.len(), self.compile_fix_round);
+
+        let topic = module.topic.clone();
+        let llm_client = self.llm_client.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.snippet_repair_task = Some(tokio::spawn(async move {
+            for (index, snippet, errors) in failing {
+                let result = llm_client.fix_compile_errors(&topic, &snippet, &errors).await;
+                if tx.send((RepairTarget::Snippet(index), result)).is_err() {
+                    break;
+                }
+            }
+        }));
+        self.snippet_repair_rx = Some(rx);
+        self.snippet_repair_message = format!("Fix code snippets that did not compile (round {})", self.compile_fix_round);
+        self.snippet_repair_paths.clear();
+    }
+
+    fn cancel_compile_check(&mut self) {
+        if let Some(task) = self.compile_check_task.take() {
+            task.abort();
+        }
+        self.compile_check_rx = None;
+    }
+
+    // Stop regenerating snippets, e.g. because the module they belong to is being replaced
+    fn cancel_snippet_repair(&mut self) {
+        if let Some(task) = self.snippet_repair_task.take() {
+            task.abort();
+        }
+        self.snippet_repair_rx = None;
+    }
+
+    // True while the selected snippet is being built or run
+    pub fn is_running_snippet(&self) -> bool {
+        self.snippet_run_rx.is_some()
+    }
+
+    // Run the selected snippet as an example of the module's Cargo project
+    fn run_selected_snippet(&mut self) {
+        let snippet_index = self.selected_snippet;
+        let Some(path) = self
+            .current_module
+            .as_ref()
+            .filter(|module| snippet_index < module.code_snippets.len())
+            .map(|module| cargo_project::code_snippet_paths(&module.code_snippets).swap_remove(snippet_index))
+        else {
+            return;
+        };
+        self.cancel_snippet_run();
+
+        let Some(project_dir) = self.current_project_dir.clone() else {
+            self.snippet_run = Some(SnippetRun {
+                snippet_index,
+                events: vec![RunEvent::Failed("No Cargo project was created for this module".to_string())],
+            });
+            return;
+        };
+        let example = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let timeout = std::time::Duration::from_secs(self.config_service.get_config().snippet_run_timeout_secs);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.snippet_run_task = Some(tokio::spawn(async move {
+            snippet_runner::run_example(&project_dir, &example, timeout, tx).await;
+        }));
+        self.snippet_run_rx = Some(rx);
+        self.snippet_run = Some(SnippetRun {
+            snippet_index,
+            events: Vec::new(),
+        });
+    }
+
+    fn poll_snippet_run(&mut self) {
+        let (Some(rx), Some(run)) = (&mut self.snippet_run_rx, &mut self.snippet_run) else {
+            return;
+        };
+        loop {
+            match rx.try_recv() {
+                Ok(event) => {
+                    if run.events.len() >= MAX_RUN_OUTPUT_LINES {
+                        run.events.remove(0);
+                    }
+                    run.events.push(event);
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.snippet_run_rx = None;
+                    self.snippet_run_task = None;
+                    break;
                 }
             }
         }
     }
 
+    // Stop the snippet run in flight; dropping the task kills the process
+    fn cancel_snippet_run(&mut self) {
+        if let Some(task) = self.snippet_run_task.take() {
+            task.abort();
+        }
+        self.snippet_run_rx = None;
+    }
+
+    // True while the hidden tests of the selected exercise are running
+    pub fn is_checking_exercise(&self) -> bool {
+        self.exercise_check_rx.is_some()
+    }
+
+    // Run the hidden tests of the selected exercise against the learner's file, or the exercise itself without tests
+    fn check_selected_exercise(&mut self) {
+        let exercise_index = self.selected_exercise;
+        let Some(module) = self.current_module.as_ref().filter(|module| exercise_index < module.exercises.len()) else {
+            return;
+        };
+        let Some(project_dir) = self.current_project_dir.clone() else {
+            return;
+        };
+        let path = cargo_project::exercise_paths(&module.exercises).swap_remove(exercise_index);
+        let bin = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let timeout = std::time::Duration::from_secs(self.config_service.get_config().snippet_run_timeout_secs);
+        if let Some(task) = self.exercise_check_task.take() {
+            task.abort();
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.exercise_check_task = Some(tokio::spawn(async move {
+            let _ = tx.send(grader::grade_exercise(&project_dir, &bin, &path, timeout).await);
+        }));
+        self.exercise_check_rx = Some(rx);
+        self.exercise_check = Some(ExerciseCheck {
+            exercise_index,
+            report: None,
+        });
+    }
+
+    fn poll_exercise_check(&mut self) {
+        let Some(rx) = &mut self.exercise_check_rx else {
+            return;
+        };
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::error::TryRecvError::Empty) => return,
+            Err(mpsc::error::TryRecvError::Disconnected) => Err(anyhow::anyhow!("Exercise check task disconnected")),
+        };
+        self.exercise_check_rx = None;
+        self.exercise_check_task = None;
+
+        let report = result.unwrap_or_else(|err| {
+            tracing::error!("Failed to check exercise: {}", err);
+            GradeReport {
+                tests: Vec::new(),
+                errors: vec![err.to_string()],
+            }
+        });
+        if let Some(check) = &mut self.exercise_check {
+            check.report = Some(report);
+        }
+    }
+
+    fn cancel_exercise_check(&mut self) {
+        if let Some(task) = self.exercise_check_task.take() {
+            task.abort();
+        }
+        self.exercise_check_rx = None;
+    }
+
+    // True while watch mode grades the current exercise
+    pub fn is_watch_checking(&self) -> bool {
+        self.watch_check_rx.is_some()
+    }
+
+    // Enter watch mode on the exercises of the current module's project
+    fn start_watch(&mut self) {
+        let Some(project_dir) = &self.current_project_dir else {
+            return;
+        };
+        match WatchSession::open(project_dir) {
+            Ok(session) => {
+                self.watch_session = Some(session);
+                self.watch_scroll = 0;
+                self.current_state = AppState::Watch;
+            }
+            Err(err) => tracing::error!("Failed to start watch mode: {}", err),
+        }
+    }
+
+    // Collect a finished grade, then grade again when the current exercise changed on disk
+    fn poll_watch(&mut self) {
+        let Some(session) = &mut self.watch_session else {
+            return;
+        };
+        if let Some(rx) = &mut self.watch_check_rx {
+            match rx.try_recv() {
+                Ok(report) => {
+                    session.record(report);
+                    self.watch_scroll = 0;
+                }
+                Err(mpsc::error::TryRecvError::Empty) => return,
+                Err(mpsc::error::TryRecvError::Disconnected) => {}
+            }
+            self.watch_check_rx = None;
+            self.watch_check_task = None;
+        }
+
+        if session.is_done() || !session.needs_check() {
+            return;
+        }
+        let timeout = std::time::Duration::from_secs(self.config_service.get_config().snippet_run_timeout_secs);
+        let grade = session.grade_current(timeout);
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.watch_check_task = Some(tokio::spawn(async move {
+            let _ = tx.send(grade.await);
+        }));
+        self.watch_check_rx = Some(rx);
+    }
+
+    fn stop_watch(&mut self) {
+        if let Some(task) = self.watch_check_task.take() {
+            task.abort();
+        }
+        self.watch_check_rx = None;
+        self.watch_session = None;
+        self.current_state = AppState::Learning;
+    }
+
+    // Abort the learning module request in flight; anything it already sent is dropped with the channel
+    fn cancel_module_generation(&mut self) {
+        if let Some(task) = self.module_task.take() {
+            task.abort();
+        }
+        self.module_receiver = None;
+        self.module_streaming = false;
+        self.llm_client.clear_retry_status();
+    }
+
+    fn cancel_question_generation(&mut self) {
+        if let Some(task) = self.question_task.take() {
+            task.abort();
+        }
+        self.question_generation_rx = None;
+        self.llm_client.clear_retry_status();
+    }
+
+    fn cancel_application_generation(&mut self) {
+        if let Some(task) = self.application_task.take() {
+            task.abort();
+        }
+        self.application_generation_rx = None;
+        self.llm_client.clear_retry_status();
+    }
+
+    // Cancel key on the loading screens: abort the request and return to where it was started
+    fn handle_generation_keys(&mut self, key_event: KeyEvent) {
+        if !matches!(key_event.code, KeyCode::Esc | KeyCode::Char('c')) {
+            return;
+        }
+
+        match self.current_state {
+            AppState::Loading => {
+                self.cancel_module_generation();
+                self.current_state = if self.current_module.is_some() {
+                    AppState::Learning
+                } else {
+                    AppState::Welcome
+                };
+            }
+            AppState::QuestionGeneration => {
+                self.cancel_question_generation();
+                // Questions for a new index are requested before any module exists
+                self.current_state = if self.current_module.is_some() {
+                    AppState::Learning
+                } else {
+                    AppState::IndexSelection
+                };
+            }
+            AppState::ApplicationGeneration => {
+                self.cancel_application_generation();
+                self.current_state = AppState::QuestionAnswering;
+            }
+            _ => {}
+        }
+    }
+
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
         // Global keybindings
         if self.show_help {
@@ -410,16 +1114,20 @@ impl App {
             return Ok(());
         }
 
+        // While typing a model search query every key belongs to the query
+        if self.current_state == AppState::Settings && self.model_picker.searching {
+            self.handle_model_picker_keys(key_event);
+            return Ok(());
+        }
+
         match key_event.code {
             KeyCode::Char('q') => self.show_quit_confirmation = true,
             KeyCode::Char('?') => self.show_help = true,
-            KeyCode::Char('s') => {
-                // Toggle settings screen if not already in settings
-                if self.current_state != AppState::Settings {
-                    self.current_state = AppState::Settings;
-                    self.settings_cursor = 0;
-                    self.settings_section = SettingsSection::LearningResources;
-                }
+            // Toggle settings screen if not already in settings
+            KeyCode::Char('s') if self.current_state != AppState::Settings => {
+                self.current_state = AppState::Settings;
+                self.settings_cursor = 0;
+                self.settings_section = SettingsSection::LearningResources;
             },
             _ => {}
         }
@@ -429,17 +1137,17 @@ impl App {
             AppState::Welcome => self.handle_welcome_keys(key_event),
             AppState::IndexSelection => self.handle_index_selection_keys(key_event),
             AppState::Learning => self.handle_learning_keys(key_event),
-            AppState::Settings => self.handle_settings_keys(key_event),
-            AppState::QuestionGeneration => {
-                // No key handling in question generation state
-                // This state is transient and will automatically transition to QuestionAnswering
+            AppState::Settings => {
+                self.handle_settings_keys(key_event);
+                self.llm_client.set_config(self.config_service.get_config().clone());
+            }
+            // Generation states transition on their own once the result arrives; only cancelling is possible
+            AppState::Loading | AppState::QuestionGeneration | AppState::ApplicationGeneration => {
+                self.handle_generation_keys(key_event)
             },
             AppState::QuestionAnswering => self.handle_question_answering_keys(key_event),
-            AppState::ApplicationGeneration => {
-                // No key handling in application generation state
-                // This state is transient and will automatically transition to ApplicationDisplay
-            },
             AppState::ApplicationDisplay => self.handle_application_display_keys(key_event),
+            AppState::Watch => self.handle_watch_keys(key_event),
             _ => {}
         }
         Ok(())
@@ -511,24 +1219,38 @@ impl App {
         // Get a random topic based on the user's level and selected index
         match data::get_random_topic_for_level(self.selected_level, &self.selected_index) {
             Ok(topic) => {
-                // Clone the sender and topic for the async task
-                let sender = self.module_sender.clone();
+                // A new module supersedes any generation still in flight
+                self.cancel_module_generation();
+                self.cancel_snippet_repair();
+                self.cancel_compile_check();
+                self.compile_report = None;
+                self.compile_fix_round = 0;
+                self.cancel_snippet_run();
+                self.snippet_run = None;
+                self.selected_snippet = 0;
+                self.cancel_exercise_check();
+                self.exercise_check = None;
+                self.selected_exercise = 0;
+                let (sender, receiver) = mpsc::channel(10);
+                self.module_receiver = Some(receiver);
+
+                // Clone the topic for the async task
                 let topic_clone = topic.clone();
                 let level = self.selected_level;
                 let llm_client = self.llm_client.clone();
 
                 // Spawn an async task to call the LLM
-                tokio::spawn(async move {
-                    // Call the LLM to generate a learning module
+                self.module_task = Some(tokio::spawn(async move {
+                    // Call the LLM to generate a learning module, streaming partial results
                     let result = llm_client
-                        .generate_learning_module(&topic_clone, level)
+                        .stream_learning_module(&topic_clone, level, &sender)
                         .await;
 
                     // Send the result back to the main thread
-                    if let Err(e) = sender.send(result).await {
+                    if let Err(e) = sender.send(ModuleUpdate::Complete(result)).await {
                         tracing::error!("Failed to send learning module: {}", e);
                     }
-                });
+                }));
 
                 // The app remains in the Loading state until the async task completes
                 // The tick method will handle the response when it arrives
@@ -546,6 +1268,8 @@ impl App {
                     code_snippets: vec![],
                     exercises: vec![],
                     additional_resources: None,
+                    model: None,
+                    diagnostics: Vec::new(),
                 };
 
                 // Set the current module
@@ -571,14 +1295,54 @@ impl App {
                 // Generate questions
                 self.generate_questions();
             }
+            // Stop a module that is still streaming in, keeping the text received so far
+            KeyCode::Esc | KeyCode::Char('c') if self.module_streaming => {
+                self.cancel_module_generation();
+            }
+            KeyCode::Char('r') if !self.module_streaming => {
+                // Regenerate the selected snippet if it failed to parse
+                self.regenerate_selected_snippet();
+            }
+            KeyCode::Char('R') if !self.module_streaming => {
+                self.regenerate_selected_exercise();
+            }
+            KeyCode::Char('c') if self.is_running_snippet() => {
+                self.cancel_snippet_run();
+                if let Some(run) = &mut self.snippet_run {
+                    run.events.push(RunEvent::Failed("Stopped".to_string()));
+                }
+            }
+            KeyCode::Tab | KeyCode::BackTab if !self.module_streaming => {
+                let count = self.current_module.as_ref().map_or(0, |module| module.code_snippets.len());
+                if count > 0 {
+                    self.selected_snippet = if key_event.code == KeyCode::Tab {
+                        (self.selected_snippet + 1) % count
+                    } else {
+                        (self.selected_snippet + count - 1) % count
+                    };
+                }
+            }
+            KeyCode::Char('x') if !self.module_streaming => {
+                self.run_selected_snippet();
+            }
+            KeyCode::Char('e') if !self.module_streaming => {
+                let count = self.current_module.as_ref().map_or(0, |module| module.exercises.len());
+                if count > 0 {
+                    self.selected_exercise = (self.selected_exercise + 1) % count;
+                }
+            }
+            KeyCode::Char('t') if !self.module_streaming && !self.is_checking_exercise() => {
+                self.check_selected_exercise();
+            }
+            KeyCode::Char('m') if !self.module_streaming => {
+                self.start_watch();
+            }
             KeyCode::Esc => {
                 self.current_state = AppState::Welcome;
             }
             KeyCode::Up | KeyCode::Char('k') => {
                 // Scroll up (if scroll_offset > 0)
-                if self.scroll_offset > 0 {
-                    self.scroll_offset -= 1;
-                }
+                self.scroll_offset = self.scroll_offset.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
                 // Scroll down (we don't know the max scroll, so we don't limit it)
@@ -588,6 +1352,38 @@ impl App {
         }
     }
 
+    fn handle_watch_keys(&mut self, key_event: KeyEvent) {
+        let Some(session) = &mut self.watch_session else {
+            return;
+        };
+        let count = session.exercises.len();
+        match key_event.code {
+            KeyCode::Esc => {
+                self.stop_watch();
+                return;
+            }
+            KeyCode::Right | KeyCode::Char('l') => session.select((session.current + 1) % count),
+            KeyCode::Left | KeyCode::Char('h') => session.select((session.current + count - 1) % count),
+            KeyCode::Char('r') => session.recheck(),
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.watch_scroll = self.watch_scroll.saturating_sub(1);
+                return;
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.watch_scroll += 1;
+                return;
+            }
+            _ => return,
+        }
+
+        // A grade still running belongs to the previous exercise or an older version of the file
+        if let Some(task) = self.watch_check_task.take() {
+            task.abort();
+        }
+        self.watch_check_rx = None;
+        self.watch_scroll = 0;
+    }
+
     // Getter methods for config service
     pub fn get_learning_resources(&self) -> &crate::config::LearningResources {
         self.config_service.get_learning_resources()
@@ -736,6 +1532,14 @@ impl App {
     }
 
     fn handle_settings_keys(&mut self, key_event: KeyEvent) {
+        // The model picker has its own navigation, only Tab and Esc leave it
+        if self.settings_section == SettingsSection::Model
+            && !matches!(key_event.code, KeyCode::Tab | KeyCode::Esc)
+        {
+            self.handle_model_picker_keys(key_event);
+            return;
+        }
+
         match key_event.code {
             KeyCode::Esc => {
                 // Return to previous screen
@@ -747,15 +1551,20 @@ impl App {
                     SettingsSection::LearningResources => SettingsSection::ContentCustomization,
                     SettingsSection::ContentCustomization => SettingsSection::LearningGoals,
                     SettingsSection::LearningGoals => SettingsSection::QuestionGenerator,
-                    SettingsSection::QuestionGenerator => SettingsSection::LearningResources,
+                    SettingsSection::QuestionGenerator => SettingsSection::Model,
+                    SettingsSection::Model => SettingsSection::Usage,
+                    SettingsSection::Usage => SettingsSection::LearningResources,
                 };
                 self.settings_cursor = 0; // Reset cursor when changing sections
+                if self.settings_section == SettingsSection::Model
+                    && self.model_picker.models.is_empty()
+                {
+                    self.load_models();
+                }
             }
             KeyCode::Up | KeyCode::Char('i') => {
                 // Move cursor up
-                if self.settings_cursor > 0 {
-                    self.settings_cursor -= 1;
-                }
+                self.settings_cursor = self.settings_cursor.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('m') => {
                 // Move cursor down
@@ -764,6 +1573,8 @@ impl App {
                     SettingsSection::ContentCustomization => 2, // 3 options (0-2)
                     SettingsSection::LearningGoals => 3, // 4 options (0-3)
                     SettingsSection::QuestionGenerator => 1, // Two options (0-1)
+                    SettingsSection::Model => 0, // Handled by the model picker
+                    SettingsSection::Usage => 0, // Read-only
                 };
                 if self.settings_cursor < max_cursor {
                     self.settings_cursor += 1;
@@ -801,11 +1612,12 @@ impl App {
                     },
                     SettingsSection::QuestionGenerator => {
                         match self.settings_cursor {
-                            0 => { let _ = self.config_service.increment_num_questions(); },
+                            0 => self.config_service.increment_num_questions(),
                             1 => { let _ = self.config_service.cycle_question_type(); },
                             _ => {}
                         }
                     }
+                    SettingsSection::Model | SettingsSection::Usage => {}
                 }
             },
             KeyCode::Left | KeyCode::Char('j')  => {
@@ -840,17 +1652,69 @@ impl App {
                     },
                     SettingsSection::QuestionGenerator => {
                         match self.settings_cursor {
-                            0 => { let _ = self.config_service.decrement_num_questions(); },
+                            0 => self.config_service.decrement_num_questions(),
                             1 => { let _ = self.config_service.cycle_question_type(); },
                             _ => {}
                         }
                     }
+                    SettingsSection::Model | SettingsSection::Usage => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_model_picker_keys(&mut self, key_event: KeyEvent) {
+        if self.model_picker.searching {
+            match key_event.code {
+                KeyCode::Esc | KeyCode::Enter => self.model_picker.searching = false,
+                KeyCode::Backspace => self.model_picker.pop_query(),
+                KeyCode::Up => self.model_picker.move_up(),
+                KeyCode::Down => self.model_picker.move_down(),
+                KeyCode::Char(c) => self.model_picker.push_query(c),
+                _ => {}
+            }
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('i') => self.model_picker.move_up(),
+            KeyCode::Down | KeyCode::Char('m') => self.model_picker.move_down(),
+            KeyCode::Char('/') => self.model_picker.searching = true,
+            KeyCode::Char('f') => self.model_picker.toggle_free_only(),
+            KeyCode::Char('r') => self.load_models(),
+            KeyCode::Enter => {
+                if let Some(model) = self.model_picker.selected() {
+                    let id = model.id.clone();
+                    if let Err(err) = self.config_service.update_model(id) {
+                        tracing::error!("Failed to save model selection: {}", err);
+                    }
                 }
             }
             _ => {}
         }
     }
 
+    // Fetch the provider's model list in the background
+    fn load_models(&mut self) {
+        let llm_client = self.llm_client.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let _ = tx.send(llm_client.list_models().await);
+        });
+        self.model_picker.loading = true;
+        self.model_picker.error = None;
+        self.model_list_rx = Some(rx);
+    }
+
+    pub fn usage(&self) -> UsageSnapshot {
+        self.llm_client.usage()
+    }
+
+    pub fn current_model(&self) -> &str {
+        &self.config_service.get_config().model
+    }
+
     pub fn get_learning_goal(&self) -> LearningGoal {
         self.config_service.get_content_customization().learning_goal
     }
@@ -872,12 +1736,13 @@ impl App {
         if let Some(generator) = &self.question_generator {
             let generator_clone = generator.clone();
             let topic_clone = topic.clone();
-            let learning_goal_clone = learning_goal.clone();
+            let learning_goal_clone = learning_goal;
             
-            // Spawn async task to generate questions
+            // Spawn async task to generate questions, replacing any request still in flight
+            self.cancel_question_generation();
             let (tx, rx) = mpsc::unbounded_channel();
             
-            tokio::spawn(async move {
+            self.question_task = Some(tokio::spawn(async move {
                 match generator_clone.generate_questions(&topic_clone, &learning_goal_clone, question_type, num_questions).await {
                     Ok(question_set) => {
                         let _ = tx.send(Ok(question_set));
@@ -886,7 +1751,7 @@ impl App {
                         let _ = tx.send(Err(err));
                     }
                 }
-            });
+            }));
             
             // Store the receiver for polling in the update loop
             self.question_generation_rx = Some(rx);
@@ -902,10 +1767,11 @@ impl App {
                 let generator_clone = generator.clone();
                 let question_set_clone = question_set.clone();
                 
-                // Spawn async task to generate application
+                // Spawn async task to generate application, replacing any request still in flight
+                self.cancel_application_generation();
                 let (tx, rx) = mpsc::unbounded_channel();
                 
-                tokio::spawn(async move {
+                self.application_task = Some(tokio::spawn(async move {
                     match generator_clone.generate_application(&question_set_clone).await {
                         Ok(application) => {
                             let _ = tx.send(Ok(application));
@@ -914,7 +1780,7 @@ impl App {
                             let _ = tx.send(Err(err));
                         }
                     }
-                });
+                }));
                 
                 // Store the receiver for polling in the update loop
                 self.application_generation_rx = Some(rx);
@@ -937,11 +1803,9 @@ impl App {
                     // Go to the next question
                     question_set.next_question();
                 },
-                KeyCode::Enter => {
-                    // If all questions are answered, generate the application
-                    if question_set.is_complete() {
-                        self.generate_application();
-                    }
+                // If all questions are answered, generate the application
+                KeyCode::Enter if question_set.is_complete() => {
+                    self.generate_application();
                 },
                 KeyCode::Char(c) => {
                     // Handle answer selection
@@ -978,7 +1842,8 @@ impl App {
             KeyCode::Enter => {
                 // Create a Cargo project from the generated application
                 if let Some(application) = &self.generated_application {
-                    match crate::cargo_project::create_application_project(application) {
+                    let resolver = CrateResolver::load();
+                    match crate::cargo_project::create_application_project(application, &self.config_service.get_config().projects, &resolver) {
                         Ok(project_dir) => {
                             tracing::info!("Created application project at: {:?}", project_dir);
                             // Go back to the learning state
@@ -995,3 +1860,370 @@ impl App {
         }
     }
 }
+
+// The end-to-end flow is tested here rather than under tests/: the crate is a binary without a library
+// target, so integration tests could only drive the compiled executable, not `App` and its key handling
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ProjectSettings};
+    use crate::llm_provider::MockProvider;
+    use crossterm::event::KeyModifiers;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // Mock LLM calls finish within a few ticks; cargo builds and runs can take several seconds
+    const LLM_TIMEOUT: Duration = Duration::from_secs(5);
+    const CARGO_TIMEOUT: Duration = Duration::from_secs(30);
+
+    fn mock_app() -> (App, tempfile::TempDir) {
+        mock_app_with_fixtures(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/llm"))
+    }
+
+    // The app runs on the default config, never the developer's ~/rust-mentor.conf, and generated
+    // projects go to the returned directory, which is removed when it is dropped
+    fn mock_app_with_fixtures(fixtures: PathBuf) -> (App, tempfile::TempDir) {
+        let output_dir = tempfile::tempdir().unwrap();
+        let config = Config {
+            projects: ProjectSettings {
+                output_dir: Some(output_dir.path().to_path_buf()),
+                workspace: false,
+                git: true,
+            },
+            ..Config::default()
+        };
+        let llm_client = LlmClient::with_provider(Arc::new(MockProvider::new(fixtures)), config.clone());
+        (App::with_llm_client(llm_client, ConfigService::in_memory(config)), output_dir)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key_event(KeyEvent::new(code, KeyModifiers::NONE)).unwrap();
+    }
+
+    // Tick the app until `done` holds, giving the spawned tasks up to `timeout` to run
+    async fn tick_until(app: &mut App, timeout: Duration, done: impl Fn(&App) -> bool) {
+        let start = std::time::Instant::now();
+        while start.elapsed() < timeout {
+            app.tick();
+            if done(app) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("Timed out waiting for the app to reach the expected state");
+    }
+
+    // Request a module from the learning view and wait until it and its project are in place
+    async fn load_module(app: &mut App) -> LearningModule {
+        app.current_state = AppState::Learning;
+        press(app, KeyCode::Char('n'));
+        assert!(app.current_state == AppState::Loading);
+        tick_until(app, LLM_TIMEOUT, |app| {
+            app.current_state == AppState::Learning && !app.module_streaming
+        })
+        .await;
+        app.current_module.clone().expect("module should be loaded")
+    }
+
+    // Replace the starter code of an exercise with its reference solution, as the learner would
+    fn solve_exercise(project_dir: &Path, exercise: &Exercise, file: &str) {
+        let path = project_dir.join(file);
+        let starter = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, starter.replace(&exercise.starter_code, &exercise.solution)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn module_flow_against_mock_provider() {
+        let (mut app, _output_dir) = mock_app();
+
+        // Welcome -> IndexSelection
+        press(&mut app, KeyCode::Enter);
+        assert!(app.current_state == AppState::IndexSelection);
+
+        // Pick Rust By Example, which starts question generation
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.current_state == AppState::QuestionGeneration);
+        tick_until(&mut app, LLM_TIMEOUT, |app| app.current_state == AppState::QuestionAnswering).await;
+
+        // Back to Learning and request a module
+        press(&mut app, KeyCode::Esc);
+        assert!(app.current_state == AppState::Learning);
+        let module = load_module(&mut app).await;
+
+        assert!(module.explanation.starts_with("Ownership and Moves"));
+        assert_eq!(module.code_snippets.len(), 2);
+        assert_eq!(module.code_snippets[1].title, "Borrowing Instead of Moving");
+        assert_eq!(module.exercises.len(), 2);
+        assert_eq!(module.exercises[0].name, "Fix the Move");
+        assert_eq!(module.exercises[0].description, "Change `describe` so it compiles without cloning.");
+        assert_eq!(module.exercises[0].hints.len(), 2);
+        assert_eq!(module.exercises[0].expected_output.as_deref(), Some("Ferris Ferris"));
+        assert!(module.exercises[0].starter_code.starts_with("fn describe("));
+        // Exercises without sub-sections keep their whole body as starter code
+        assert!(module.exercises[1].problem_statement.is_empty());
+        assert!(module.exercises[1].starter_code.starts_with("fn count_chars"));
+        assert!(!module.code_snippets[0].code.contains("// code snippet:"));
+        assert!(!module.code_snippets[0].description.is_empty());
+        assert!(module.code_snippets.iter().all(|snippet| snippet.syntax_error.is_none()));
+        assert!(module.model.is_some());
+    }
+
+    #[tokio::test]
+    async fn application_flow_against_mock_provider() {
+        let (mut app, _output_dir) = mock_app();
+        let module = load_module(&mut app).await;
+
+        // Learning -> QuestionGeneration -> QuestionAnswering
+        press(&mut app, KeyCode::Char('w'));
+        assert!(app.current_state == AppState::QuestionGeneration);
+        tick_until(&mut app, LLM_TIMEOUT, |app| app.current_state == AppState::QuestionAnswering).await;
+
+        let question_set = app.question_set.clone().expect("questions should be loaded");
+        assert_eq!(question_set.topic, module.topic);
+        assert_eq!(question_set.questions.len(), 2);
+        assert_eq!(question_set.questions[0].options.len(), 4);
+
+        // Answer both questions and submit
+        press(&mut app, KeyCode::Char('2'));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('y'));
+        assert!(app.question_set.as_ref().unwrap().is_complete());
+        press(&mut app, KeyCode::Enter);
+        assert!(app.current_state == AppState::ApplicationGeneration);
+        tick_until(&mut app, LLM_TIMEOUT, |app| app.current_state == AppState::ApplicationDisplay).await;
+
+        let application = app.generated_application.clone().expect("application should be generated");
+        assert_eq!(application.name, "Ferris Todo");
+        assert_eq!(application.features.len(), 2);
+        let paths: Vec<&str> = application.files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, ["src/main.rs", "src/store/mod.rs", "Cargo.toml"]);
+        assert!(application.files[1].content.contains("\n    fs::read_to_string(path)\n"));
+        assert_eq!(application.main_file().unwrap().path, "src/main.rs");
+    }
+
+    #[tokio::test]
+    async fn generated_project_is_compile_checked() {
+        let (mut app, output_dir) = mock_app();
+        load_module(&mut app).await;
+        assert!(app.current_project_dir.as_ref().unwrap().starts_with(output_dir.path()));
+
+        tick_until(&mut app, CARGO_TIMEOUT, |app| app.compile_report.is_some()).await;
+        let report = app.compile_report.clone().unwrap();
+        assert!(report.project_errors.is_empty(), "{:?}", report.project_errors);
+        assert_eq!(report.files.len(), 4);
+        assert!(report.files.iter().filter(|file| !file.is_exercise()).all(|file| file.passed()));
+        let fix_the_move = report.files.iter().find(|file| file.path.ends_with("fix_the_move.rs"));
+        assert!(fix_the_move.is_some_and(|file| file.failed()), "{:?}", report.files);
+    }
+
+    #[tokio::test]
+    async fn selected_snippet_runs_in_the_project() {
+        let (mut app, _output_dir) = mock_app();
+        load_module(&mut app).await;
+
+        // Select the second snippet and run it
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('x'));
+        tick_until(&mut app, CARGO_TIMEOUT, |app| !app.is_running_snippet()).await;
+        let run = app.snippet_run.as_ref().expect("snippet should have run");
+        assert_eq!(run.snippet_index, 1);
+        assert!(run.events.contains(&RunEvent::Stdout("hello has 5 bytes".to_string())), "{:?}", run.events);
+        assert_eq!(run.events.last(), Some(&RunEvent::Exited(Some(0))));
+    }
+
+    #[tokio::test]
+    async fn reference_solution_passes_the_hidden_tests() {
+        let (mut app, _output_dir) = mock_app();
+        let module = load_module(&mut app).await;
+
+        // The starter code of the first exercise does not build, its reference solution passes the hidden tests
+        assert!(module.exercises[0].tests.contains("fn repeats_the_name()"));
+        assert!(module.exercises[1].tests.is_empty());
+        let project_dir = app.current_project_dir.clone().unwrap();
+        assert!(project_dir.join("grading/fix_the_move.rs").exists());
+        assert!(project_dir.join("solutions/fix_the_move.rs").exists());
+
+        press(&mut app, KeyCode::Char('t'));
+        tick_until(&mut app, CARGO_TIMEOUT, |app| !app.is_checking_exercise()).await;
+        let report = app.exercise_check.as_ref().and_then(|check| check.report.clone()).unwrap();
+        assert!(!report.errors.is_empty());
+
+        solve_exercise(&project_dir, &module.exercises[0], "src/bin/fix_the_move.rs");
+        press(&mut app, KeyCode::Char('t'));
+        tick_until(&mut app, CARGO_TIMEOUT, |app| !app.is_checking_exercise()).await;
+        let report = app.exercise_check.as_ref().and_then(|check| check.report.clone()).unwrap();
+        assert!(report.passed(), "{:?}", report);
+        assert_eq!(report.tests[0].name, "repeats_the_name");
+    }
+
+    #[tokio::test]
+    async fn watch_mode_moves_on_after_a_pass() {
+        let (mut app, _output_dir) = mock_app();
+        let module = load_module(&mut app).await;
+        let project_dir = app.current_project_dir.clone().unwrap();
+        solve_exercise(&project_dir, &module.exercises[0], "src/bin/fix_the_move.rs");
+
+        // Watch mode passes the solved exercise and moves on to the next, which is not done yet
+        press(&mut app, KeyCode::Char('m'));
+        assert!(app.current_state == AppState::Watch);
+        tick_until(&mut app, CARGO_TIMEOUT, |app| {
+            app.watch_session.as_ref().is_some_and(|session| session.exercises[1].report.is_some())
+        })
+        .await;
+        let session = app.watch_session.as_ref().unwrap();
+        assert!(session.exercises[0].passed());
+        assert_eq!(session.current, 1);
+        assert_eq!(session.just_passed, None);
+        assert!(!session.exercises[1].passed());
+        let report = session.exercises[1].report.as_ref().unwrap();
+        assert!(report.errors.iter().any(|error| error.contains(grader::NOT_DONE_MARKER)), "{:?}", report);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.current_state == AppState::Learning);
+    }
+
+    #[tokio::test]
+    async fn generated_project_is_committed() {
+        let (mut app, _output_dir) = mock_app();
+        let module = load_module(&mut app).await;
+        let project_dir = app.current_project_dir.clone().unwrap();
+
+        // The generated starter is committed, so the learner's solution shows up as a change against it
+        solve_exercise(&project_dir, &module.exercises[0], "src/bin/fix_the_move.rs");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git").current_dir(&project_dir).args(args).output().unwrap();
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        };
+        assert_eq!(git(&["log", "--format=%s"]).trim(), format!("Generate learning module: {}", module.topic));
+        assert_eq!(git(&["diff", "--name-only"]), "src/bin/fix_the_move.rs\n");
+    }
+
+    #[tokio::test]
+    async fn model_picker_search_captures_global_keys() {
+        let (mut app, _output_dir) = mock_app();
+        app.current_state = AppState::Settings;
+        for _ in 0..4 {
+            press(&mut app, KeyCode::Tab);
+        }
+        assert!(app.settings_section == SettingsSection::Model);
+        tick_until(&mut app, LLM_TIMEOUT, |app| !app.model_picker.loading).await;
+        assert_eq!(app.model_picker.filtered().len(), 1);
+
+        // 'q' and 's' are part of the query while searching
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Char('q'));
+        press(&mut app, KeyCode::Char('s'));
+        assert!(!app.show_quit_confirmation);
+        assert_eq!(app.model_picker.query, "qs");
+        assert!(app.model_picker.filtered().is_empty());
+
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Enter);
+        assert!(!app.model_picker.searching);
+        assert_eq!(app.model_picker.selected().unwrap().id, "mock");
+
+        // Mock models are not free, so the filter hides them
+        press(&mut app, KeyCode::Char('f'));
+        assert!(app.model_picker.selected().is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelled_generation_results_are_discarded() {
+        let (mut app, _output_dir) = mock_app();
+
+        app.current_state = AppState::Learning;
+        press(&mut app, KeyCode::Char('n'));
+        assert!(app.current_state == AppState::Loading);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.current_state == AppState::Welcome);
+
+        app.current_state = AppState::Learning;
+        press(&mut app, KeyCode::Char('w'));
+        assert!(app.current_state == AppState::QuestionGeneration);
+        press(&mut app, KeyCode::Char('c'));
+        assert!(app.current_state == AppState::IndexSelection);
+
+        // Give the aborted tasks time to finish had they not been cancelled
+        for _ in 0..20 {
+            app.tick();
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(app.current_state == AppState::IndexSelection);
+        assert!(app.current_module.is_none());
+        assert!(app.question_set.is_none());
+    }
+
+    #[tokio::test]
+    async fn only_the_selected_snippet_or_exercise_is_regenerated() {
+        let (mut app, _output_dir) = mock_app();
+        let broken = || syntax_check::check("fn main( {").err();
+        let snippet = |title: &str| CodeSnippet {
+            title: title.to_string(),
+            description: String::new(),
+            code: "fn main( {".to_string(),
+            syntax_error: broken(),
+        };
+        let exercise = Exercise {
+            name: "Broken".to_string(),
+            description: String::new(),
+            problem_statement: String::new(),
+            hints: Vec::new(),
+            expected_output: None,
+            starter_code: "fn main( {".to_string(),
+            tests: String::new(),
+            solution: String::new(),
+            syntax_error: broken(),
+        };
+        app.current_module = Some(LearningModule {
+            topic: "Borrowing".to_string(),
+            explanation: String::new(),
+            code_snippets: vec![snippet("First"), snippet("Second")],
+            exercises: vec![exercise],
+            additional_resources: None,
+            model: None,
+            diagnostics: Vec::new(),
+        });
+        app.current_state = AppState::Learning;
+        let project_dir = cargo_project::create_cargo_project(
+            app.current_module.as_ref().unwrap(),
+            app.selected_level,
+            &app.config_service.get_config().projects,
+            &CrateResolver::load(),
+        )
+        .unwrap();
+        // The learner's edits to the other example must survive the repair
+        std::fs::write(project_dir.join("examples/first.rs"), "// edited by the learner\n").unwrap();
+        app.current_project_dir = Some(project_dir.clone());
+
+        press(&mut app, KeyCode::Tab);
+        press(&mut app, KeyCode::Char('r'));
+        tick_until(&mut app, LLM_TIMEOUT, |app| !app.is_repairing_snippets()).await;
+        let module = app.current_module.as_ref().unwrap();
+        assert!(module.code_snippets[0].syntax_error.is_some());
+        assert!(module.code_snippets[1].syntax_error.is_none());
+        assert!(module.code_snippets[1].code.contains("Ferris"));
+        assert!(module.exercises[0].syntax_error.is_some());
+        assert_eq!(std::fs::read_to_string(project_dir.join("examples/first.rs")).unwrap(), "// edited by the learner\n");
+        assert!(std::fs::read_to_string(project_dir.join("examples/second.rs")).unwrap().contains("Ferris"));
+
+        press(&mut app, KeyCode::Char('R'));
+        tick_until(&mut app, LLM_TIMEOUT, |app| !app.is_repairing_snippets()).await;
+        let module = app.current_module.as_ref().unwrap();
+        assert!(module.exercises[0].syntax_error.is_none());
+        assert!(module.code_snippets[0].syntax_error.is_some());
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_reports_an_error_module() {
+        let (mut app, _output_dir) = mock_app_with_fixtures(PathBuf::from("tests/fixtures/does-not-exist"));
+
+        app.current_state = AppState::Learning;
+        press(&mut app, KeyCode::Char('n'));
+        tick_until(&mut app, LLM_TIMEOUT, |app| app.current_state == AppState::Learning).await;
+
+        let module = app.current_module.as_ref().unwrap();
+        assert_eq!(module.topic, "Error Generating Content");
+        assert!(module.explanation.contains("mock fixture"));
+    }
+}
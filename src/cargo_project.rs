@@ -1,14 +1,20 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
 use chrono::Local;
 use anyhow::{Result, Context};
 
 use crate::app::LearningModule;
-use crate::question_generator::GeneratedApplication;
+use crate::config::ProjectSettings;
+use crate::dependencies::{CrateResolver, Dependency};
+use crate::git;
+use crate::grader::NOT_DONE_MARKER;
+use crate::prompt_response::{CodeSnippet, Exercise};
+use crate::question_generator::{GeneratedApplication, GeneratedFile};
 
 /// Creates a Cargo project for a learning module
-pub fn create_cargo_project(module: &LearningModule, level: u8) -> Result<PathBuf> {
+pub fn create_cargo_project(module: &LearningModule, level: u8, settings: &ProjectSettings, resolver: &CrateResolver) -> Result<PathBuf> {
     // Create directory name in the format [topic]_[level]_[date]
     let current_date = Local::now().format("%Y-%m-%d").to_string();
     let topic_slug = module.topic
@@ -21,11 +27,10 @@ pub fn create_cargo_project(module: &LearningModule, level: u8) -> Result<PathBu
     );
 
     // Create the directory
-    let project_dir = PathBuf::from(&dir_name);
-    fs::create_dir_all(&project_dir).context("Failed to create project directory")?;
+    let project_dir = create_project_dir(&dir_name, settings)?;
 
     // Initialize Cargo project
-    initialize_cargo_project(&project_dir, topic_slug.as_str())?;
+    initialize_cargo_project(&project_dir, settings)?;
 
     // Create markdown file with explanation
     create_explanation_file(&project_dir, module)?;
@@ -34,21 +39,144 @@ pub fn create_cargo_project(module: &LearningModule, level: u8) -> Result<PathBu
     create_docs_explanation_file(&project_dir, module)?;
 
     // Create Rust files for code snippets
-    create_code_snippet_files(&project_dir, module)?;
+    create_code_snippet_files(&project_dir, module, resolver)?;
 
     // Create Rust files for exercises
-    create_exercise_files(&project_dir, module)?;
+    create_exercise_files(&project_dir, module, resolver)?;
+
+    // Record what the project was generated from
+    let mut manifest = Manifest::open(&project_dir)?;
+    manifest.set_metadata("topic", module.topic.as_str())?;
+    manifest.set_metadata("level", i64::from(level))?;
+    manifest.set_metadata("generated", current_date.as_str())?;
+    if let Some(model) = &module.model {
+        manifest.set_metadata("model", model.as_str())?;
+    }
+    manifest.save()?;
+
+    let model = module.model.as_deref().unwrap_or("unknown model");
+    commit_project_files(
+        &project_dir,
+        &[Path::new(".")],
+        &format!(
+            "Generate learning module: {}\n\nTopic: {}\nLevel: {}\nModel: {}\n",
+            module.topic, module.topic, level, model
+        ),
+        settings,
+    );
 
     Ok(project_dir)
 }
 
-/// Initializes a new Cargo project in the given directory
-fn initialize_cargo_project(project_dir: &Path, project_name: &str) -> Result<()> {
+/// Commits `paths` of a generated project (`.` for all of it) if projects keep a git history.
+/// Failures are only logged, since the project works without history, e.g. when git is not installed.
+pub fn commit_project_files(project_dir: &Path, paths: &[&Path], message: &str, settings: &ProjectSettings) {
+    if !settings.git {
+        return;
+    }
+    match git::init(project_dir).and_then(|()| git::commit(project_dir, paths, message)) {
+        Ok(true) => tracing::info!("Committed \"{}\" in {:?}", message.lines().next().unwrap_or_default(), project_dir),
+        Ok(false) => {}
+        Err(err) => tracing::warn!("Failed to commit generated files in {:?}: {:#}", project_dir, err),
+    }
+}
+
+/// Creates a new, empty directory named `dir_name` under the configured output directory.
+/// Names already taken get `_2`, `_3`, ... appended, so earlier projects are never overwritten.
+fn create_project_dir(dir_name: &str, settings: &ProjectSettings) -> Result<PathBuf> {
+    let root = match (&settings.output_dir, settings.workspace) {
+        (Some(output_dir), _) => output_dir.clone(),
+        // The current directory is often a package itself, e.g. under `cargo run`
+        (None, true) => anyhow::bail!("`workspace = true` needs an `output_dir` in [projects] of ~/rust-mentor.conf"),
+        (None, false) => PathBuf::new(),
+    };
+    if !root.as_os_str().is_empty() {
+        fs::create_dir_all(&root).context("Failed to create output directory")?;
+    }
+    // Checked before the project exists, so a root that cannot be a workspace leaves nothing behind
+    if settings.workspace {
+        create_workspace_manifest(&root)?;
+    }
+    unique_dir(&root, dir_name)
+}
+
+// `fs::create_dir` fails on existing directories, which makes picking a free name race-free
+fn unique_dir(root: &Path, dir_name: &str) -> Result<PathBuf> {
+    for attempt in 1.. {
+        let candidate = match attempt {
+            1 => root.join(dir_name),
+            n => root.join(format!("{}_{}", dir_name, n)),
+        };
+        match fs::create_dir(&candidate) {
+            Ok(()) => return Ok(candidate),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err).context("Failed to create project directory"),
+        }
+    }
+    unreachable!()
+}
+
+/// Writes a virtual workspace manifest to `root` unless it already has one. Projects generated there
+/// before become members, since cargo refuses to build a package inside a workspace that does not list it.
+fn create_workspace_manifest(root: &Path) -> Result<()> {
+    let manifest_path = root.join("Cargo.toml");
+    if manifest_path.exists() {
+        let manifest = fs::read_to_string(&manifest_path).context("Failed to read workspace Cargo.toml")?;
+        let manifest: toml::Table = toml::from_str(&manifest).context("Failed to parse workspace Cargo.toml")?;
+        if !manifest.contains_key("workspace") {
+            anyhow::bail!(
+                "{} is not a workspace manifest; set `output_dir` to another directory or turn `workspace` off",
+                manifest_path.display()
+            );
+        }
+        return Ok(());
+    }
+
+    let mut members: Vec<String> = fs::read_dir(root)
+        .context("Failed to read output directory")?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("Cargo.toml").is_file())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    members.sort();
+    let mut manifest = toml_edit::DocumentMut::new();
+    manifest["workspace"]["resolver"] = toml_edit::value("2");
+    manifest["workspace"]["members"] = toml_edit::value(members.into_iter().collect::<toml_edit::Array>());
+    fs::write(manifest_path, manifest.to_string()).context("Failed to write workspace Cargo.toml")
+}
+
+/// Adds `member` (a directory directly under `root`) to the members of the workspace at `root`.
+/// Does nothing if it is listed already, e.g. because `cargo init` registered it itself.
+fn register_workspace_member(root: &Path, member: &Path) -> Result<()> {
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path).context("Failed to read workspace Cargo.toml")?;
+    let mut manifest: toml_edit::DocumentMut = manifest.parse().context("Failed to parse workspace Cargo.toml")?;
+    let Some(workspace) = manifest.get_mut("workspace").and_then(toml_edit::Item::as_table_like_mut) else {
+        anyhow::bail!("{} is not a workspace manifest", manifest_path.display());
+    };
+
+    let name = member.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let members = workspace
+        .entry("members")
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()
+        .context("`workspace.members` is not an array")?;
+    if members.iter().any(|existing| existing.as_str() == Some(name.as_str())) {
+        return Ok(());
+    }
+    members.push(name);
+    fs::write(manifest_path, manifest.to_string()).context("Failed to write workspace Cargo.toml")
+}
+
+/// Initializes a new Cargo project in the given directory, named after the directory so names
+/// stay unique within a workspace, and registers it with the workspace if one is configured
+fn initialize_cargo_project(project_dir: &Path, settings: &ProjectSettings) -> Result<()> {
+    let project_name = project_dir.file_name().unwrap_or_default().to_string_lossy().to_lowercase().replace(' ', "_");
     let cargo_cmd = Command::new("cargo")
         .current_dir(project_dir)
         .arg("init")
         .arg("--name")
-        .arg(project_name.to_lowercase().replace(' ', "_"))
+        .arg(project_name)
         .output()
         .context("Failed to execute cargo init command")?;
 
@@ -57,6 +185,12 @@ fn initialize_cargo_project(project_dir: &Path, project_name: &str) -> Result<()
         anyhow::bail!("Failed to initialize Cargo project: {}", error);
     }
 
+    if settings.workspace
+        && let Some(root) = project_dir.parent()
+    {
+        register_workspace_member(root, project_dir)?;
+    }
+
     Ok(())
 }
 
@@ -83,69 +217,155 @@ fn create_docs_explanation_file(project_dir: &Path, module: &LearningModule) ->
     Ok(())
 }
 
-/// Creates Rust files for each code snippet
-fn create_code_snippet_files(project_dir: &Path, module: &LearningModule) -> Result<()> {
-    let examples_dir = project_dir.join("examples");
-    fs::create_dir_all(&examples_dir).context("Failed to create examples directory")?;
+/// Paths of the example files for the snippets, in order, relative to the project directory
+pub fn code_snippet_paths(snippets: &[CodeSnippet]) -> Vec<PathBuf> {
+    unique_stems(snippets.iter().map(|snippet| snippet.title.as_str()))
+        .into_iter()
+        .map(|stem| Path::new("examples").join(format!("{}.rs", stem)))
+        .collect()
+}
 
-    for (i, snippet) in module.code_snippets.iter().enumerate() {
-        let file_name = format!("{}.rs", sanitize_filename(&snippet.title, i + 1));
-        let file_path = examples_dir.join(file_name);
+/// Paths of the binaries for the exercises, in order, relative to the project directory
+pub fn exercise_paths(exercises: &[Exercise]) -> Vec<PathBuf> {
+    unique_stems(exercises.iter().map(|exercise| exercise.name.as_str()))
+        .into_iter()
+        .map(|stem| Path::new("src/bin").join(format!("{}.rs", stem)))
+        .collect()
+}
+
+// File stems for a list of titles; a title that sanitizes to a stem already in use gets its number appended
+fn unique_stems<'a>(titles: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    titles
+        .enumerate()
+        .map(|(i, title)| {
+            let base = sanitize_filename(title, i + 1);
+            let mut stem = base.clone();
+            let mut suffix = i + 1;
+            while !taken.insert(stem.clone()) {
+                stem = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            stem
+        })
+        .collect()
+}
 
-        let content = format!("// {}\n// {}\n\n{}", 
-            snippet.title, 
-            snippet.description, 
-            snippet.code
-        );
+/// Creates Rust files for each code snippet, replacing existing ones
+pub fn create_code_snippet_files(project_dir: &Path, module: &LearningModule, resolver: &CrateResolver) -> Result<()> {
+    let examples_dir = project_dir.join("examples");
+    fs::create_dir_all(&examples_dir).context("Failed to create examples directory")?;
 
-        fs::write(file_path, content).context("Failed to write code snippet file")?;
+    let mut manifest = Manifest::open(project_dir)?;
+    for (snippet, path) in module.code_snippets.iter().zip(code_snippet_paths(&module.code_snippets)) {
+        write_code_snippet_file(project_dir, snippet, &path, &mut manifest)?;
     }
-
-    Ok(())
+    manifest.add_dependencies_of(resolver, module.code_snippets.iter().map(|snippet| snippet.code.as_str()))?;
+    manifest.save()
 }
 
-/// Creates Rust files for each exercise
-fn create_exercise_files(project_dir: &Path, module: &LearningModule) -> Result<()> {
-    let exercises_dir = project_dir.join("src").join("bin");
-    fs::create_dir_all(&exercises_dir).context("Failed to create exercises directory")?;
-
-    for (i, exercise) in module.exercises.iter().enumerate() {
-        let file_name = format!("{}.rs", sanitize_filename(&exercise.name, i + 1));
-        let file_path = exercises_dir.join(file_name);
+/// Rewrites the example of the snippet at `index` only, keeping the learner's edits to the others
+pub fn update_code_snippet_file(project_dir: &Path, module: &LearningModule, index: usize, resolver: &CrateResolver) -> Result<()> {
+    let mut manifest = Manifest::open(project_dir)?;
+    let path = &code_snippet_paths(&module.code_snippets)[index];
+    write_code_snippet_file(project_dir, &module.code_snippets[index], path, &mut manifest)?;
+    manifest.add_dependencies_of(resolver, [module.code_snippets[index].code.as_str()])?;
+    manifest.save()
+}
 
-        let content = format!("// {}\n// {}\n\n{}", 
-            exercise.name, 
-            exercise.description, 
-            exercise.code
-        );
+fn write_code_snippet_file(project_dir: &Path, snippet: &CodeSnippet, path: &Path, manifest: &mut Manifest) -> Result<()> {
+    manifest.add_example(path)?;
+    let content = format!("// {}\n// {}\n\n{}", 
+        snippet.title, 
+        snippet.description, 
+        snippet.code
+    );
+    fs::write(project_dir.join(path), content).context("Failed to write code snippet file")
+}
 
-        fs::write(file_path, content).context("Failed to write exercise file")?;
+/// Creates Rust files for each exercise
+fn create_exercise_files(project_dir: &Path, module: &LearningModule, resolver: &CrateResolver) -> Result<()> {
+    let mut manifest = Manifest::open(project_dir)?;
+    for (exercise, path) in module.exercises.iter().zip(exercise_paths(&module.exercises)) {
+        write_exercise_file(project_dir, exercise, &path, &mut manifest)?;
     }
+    manifest.add_dependencies_of(resolver, module.exercises.iter().flat_map(|exercise| {
+        [exercise.starter_code.as_str(), exercise.tests.as_str(), exercise.solution.as_str()]
+    }))?;
+    manifest.save()
+}
 
-    // Update Cargo.toml to include the exercises as binaries
-    update_cargo_toml(project_dir, module)?;
-
-    Ok(())
+/// Rewrites the file of the exercise at `index` only, leaving the learner's work on the others alone
+pub fn update_exercise_file(project_dir: &Path, module: &LearningModule, index: usize, resolver: &CrateResolver) -> Result<()> {
+    let mut manifest = Manifest::open(project_dir)?;
+    let path = &exercise_paths(&module.exercises)[index];
+    write_exercise_file(project_dir, &module.exercises[index], path, &mut manifest)?;
+    manifest.add_dependencies_of(resolver, [module.exercises[index].starter_code.as_str()])?;
+    manifest.save()
 }
 
-/// Updates the Cargo.toml file to include the exercises as binaries
-fn update_cargo_toml(project_dir: &Path, module: &LearningModule) -> Result<()> {
-    let cargo_toml_path = project_dir.join("Cargo.toml");
-    let mut cargo_toml = fs::read_to_string(&cargo_toml_path).context("Failed to read Cargo.toml")?;
+fn write_exercise_file(project_dir: &Path, exercise: &Exercise, path: &Path, manifest: &mut Manifest) -> Result<()> {
+    let exercises_dir = project_dir.join("src").join("bin");
+    fs::create_dir_all(&exercises_dir).context("Failed to create exercises directory")?;
 
-    // Add [[bin]] sections for each exercise
-    cargo_toml.push_str("\n");
-    for (i, exercise) in module.exercises.iter().enumerate() {
-        let bin_name = sanitize_filename(&exercise.name, i + 1);
-        cargo_toml.push_str(&format!("[[bin]]\nname = \"{}\"\npath = \"src/bin/{}.rs\"\n\n", 
-            bin_name, 
-            bin_name
+    let file_path = project_dir.join(path);
+    manifest.add_bin(path)?;
+
+    // Without tests, running successfully is all it takes to pass, which the starter code may do already
+    let mut content = if exercise.tests.trim().is_empty() {
+        format!(
+            "{}//\n// Delete the next line once you are done, the exercise does not pass before that\n{}\n\n{}",
+            exercise_header(exercise),
+            NOT_DONE_MARKER,
+            exercise.starter_code
+        )
+    } else {
+        format!("{}\n{}", exercise_header(exercise), exercise.starter_code)
+    };
+
+    // Tests live outside src/ so they don't give the answer away, and are pulled in only for `cargo test`
+    let stem = file_path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    if !exercise.tests.trim().is_empty() {
+        let grading_dir = project_dir.join("grading");
+        fs::create_dir_all(&grading_dir).context("Failed to create grading directory")?;
+        fs::write(grading_dir.join(format!("{}.rs", stem)), &exercise.tests).context("Failed to write exercise tests")?;
+        content.push_str(&format!(
+            "\n// Tests used to check your solution (`cargo test --bin {stem}`)\n\
+             #[cfg(test)]\n#[path = \"../../grading/{stem}.rs\"]\nmod grading;\n"
         ));
     }
+    if !exercise.solution.trim().is_empty() {
+        let solutions_dir = project_dir.join("solutions");
+        fs::create_dir_all(&solutions_dir).context("Failed to create solutions directory")?;
+        fs::write(solutions_dir.join(format!("{}.rs", stem)), &exercise.solution)
+            .context("Failed to write exercise solution")?;
+    }
 
-    fs::write(cargo_toml_path, cargo_toml).context("Failed to write updated Cargo.toml")?;
+    fs::write(file_path, content).context("Failed to write exercise file")
+}
 
-    Ok(())
+/// Comment block with everything needed to solve the exercise from the file alone
+fn exercise_header(exercise: &Exercise) -> String {
+    let mut header = format!("// {}\n// {}\n", exercise.name, exercise.description);
+    let mut section = |title: &str, lines: Vec<String>| {
+        if lines.is_empty() {
+            return;
+        }
+        header.push_str(&format!("//\n// {}:\n", title));
+        for line in lines {
+            if line.trim().is_empty() {
+                header.push_str("//\n");
+            } else {
+                header.push_str(&format!("//   {}\n", line));
+            }
+        }
+    };
+    section("Problem", exercise.problem_statement.lines().map(str::to_string).collect());
+    section("Hints", exercise.hints.iter().map(|hint| format!("- {}", hint)).collect());
+    if let Some(expected_output) = &exercise.expected_output {
+        section("Expected output", expected_output.lines().map(str::to_string).collect());
+    }
+    header
 }
 
 /// Sanitizes a filename by removing invalid characters and ensuring it's a valid Rust identifier
@@ -163,7 +383,7 @@ fn sanitize_filename(name: &str, fallback_index: usize) -> String {
 }
 
 /// Creates a Cargo project from a generated application
-pub fn create_application_project(application: &GeneratedApplication) -> Result<PathBuf> {
+pub fn create_application_project(application: &GeneratedApplication, settings: &ProjectSettings, resolver: &CrateResolver) -> Result<PathBuf> {
     // Create directory name in the format [app_name]_[date]
     let current_date = Local::now().format("%Y-%m-%d").to_string();
     let app_name_slug = application.name
@@ -172,11 +392,10 @@ pub fn create_application_project(application: &GeneratedApplication) -> Result<
     let dir_name = format!("{}_{}", app_name_slug, current_date);
 
     // Create the directory
-    let project_dir = PathBuf::from(&dir_name);
-    fs::create_dir_all(&project_dir).context("Failed to create project directory")?;
+    let project_dir = create_project_dir(&dir_name, settings)?;
 
     // Initialize Cargo project
-    initialize_cargo_project(&project_dir, &app_name_slug)?;
+    initialize_cargo_project(&project_dir, settings)?;
 
     // Create README.md with application description
     let readme_path = project_dir.join("README.md");
@@ -187,8 +406,78 @@ pub fn create_application_project(application: &GeneratedApplication) -> Result<
     );
     fs::write(readme_path, readme_content).context("Failed to write README.md file")?;
 
-    // Create Rust files for code snippets
-    for (i, snippet) in application.code_snippets.iter().enumerate() {
+    let mut manifest = Manifest::open(&project_dir)?;
+    manifest.set_metadata("application", application.name.as_str())?;
+    manifest.set_metadata("generated", current_date.as_str())?;
+
+    if application.files.is_empty() {
+        write_application_snippets(&project_dir, &application.code_snippets, &mut manifest, resolver)?;
+    } else {
+        write_application_files(&project_dir, &application.files, &mut manifest, resolver)?;
+    }
+    manifest.save()?;
+
+    commit_project_files(
+        &project_dir,
+        &[Path::new(".")],
+        &format!("Generate application: {}\n\n{}\n", application.name, application.description),
+        settings,
+    );
+
+    Ok(project_dir)
+}
+
+/// Writes the files of an application at their paths; `Cargo.toml` is merged into the manifest rather
+/// than replacing it. Files whose path would leave the project directory are skipped.
+fn write_application_files(project_dir: &Path, files: &[GeneratedFile], manifest: &mut Manifest, resolver: &CrateResolver) -> Result<()> {
+    for file in files {
+        if file.path == "Cargo.toml" {
+            if let Err(err) = manifest.merge_fragment(&file.content) {
+                tracing::warn!("Skipping the generated Cargo.toml: {:#}", err);
+            }
+            continue;
+        }
+        let path = match project_file_path(project_dir, &file.path) {
+            Ok(path) => path,
+            Err(err) => {
+                tracing::warn!("Skipping generated file: {}", err);
+                continue;
+            }
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create directory for {}", file.path))?;
+        }
+        fs::write(&path, &file.content).with_context(|| format!("Failed to write {}", file.path))?;
+    }
+
+    let sources = files.iter().filter(|file| file.path.ends_with(".rs")).map(|file| file.content.as_str());
+    manifest.add_dependencies_of(resolver, sources)
+}
+
+/// Resolves a path chosen by the model inside `project_dir`. Absolute paths, `..` and anything inside
+/// `.git` are rejected, so a generated application can only ever write into its own project.
+fn project_file_path(project_dir: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    let mut has_name = false;
+    for component in path.components() {
+        match component {
+            Component::Normal(name) if name == ".git" => anyhow::bail!("{} points into .git", relative),
+            Component::Normal(_) => has_name = true,
+            Component::CurDir => {}
+            _ => anyhow::bail!("{} escapes the project directory", relative),
+        }
+    }
+    if !has_name {
+        anyhow::bail!("\"{}\" is not a file path", relative);
+    }
+    Ok(project_dir.join(path))
+}
+
+/// Places titled snippets of the older application format: the first or "main" one becomes `src/main.rs`,
+/// a "lib" one `src/lib.rs`, and everything else a binary under `src/bin`
+fn write_application_snippets(project_dir: &Path, snippets: &[CodeSnippet], manifest: &mut Manifest, resolver: &CrateResolver) -> Result<()> {
+    let stems = unique_stems(snippets.iter().map(|snippet| snippet.title.as_str()));
+    for (i, snippet) in snippets.iter().enumerate() {
         let is_main = i == 0 || snippet.title.to_lowercase().contains("main");
 
         if is_main {
@@ -197,7 +486,7 @@ pub fn create_application_project(application: &GeneratedApplication) -> Result<
             fs::write(main_path, &snippet.code).context("Failed to write main.rs file")?;
         } else {
             // Other snippets go to src/lib.rs or src/bin/
-            let file_name = sanitize_filename(&snippet.title, i + 1);
+            let file_name = &stems[i];
 
             if file_name.contains("lib") || snippet.title.to_lowercase().contains("lib") {
                 // Library code goes to src/lib.rs
@@ -208,30 +497,289 @@ pub fn create_application_project(application: &GeneratedApplication) -> Result<
                 let bin_dir = project_dir.join("src").join("bin");
                 fs::create_dir_all(&bin_dir).context("Failed to create bin directory")?;
 
-                let bin_path = bin_dir.join(format!("{}.rs", file_name));
-                fs::write(bin_path, &snippet.code).context("Failed to write binary file")?;
+                let bin_path = Path::new("src/bin").join(format!("{}.rs", file_name));
+                fs::write(project_dir.join(&bin_path), &snippet.code).context("Failed to write binary file")?;
 
                 // Update Cargo.toml to include the binary
-                update_cargo_toml_for_bin(&project_dir, &file_name)?;
+                manifest.add_bin(&bin_path)?;
             }
         }
     }
+    manifest.add_dependencies_of(resolver, snippets.iter().map(|snippet| snippet.code.as_str()))
+}
 
-    Ok(project_dir)
+// Tables of a generated Cargo.toml fragment that are merged; the package itself stays as `cargo init` made it
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Cargo.toml of a generated project, edited with `toml_edit` so the formatting of everything else is kept
+/// and targets, dependencies and metadata are only ever added once
+pub struct Manifest {
+    path: PathBuf,
+    document: toml_edit::DocumentMut,
 }
 
-/// Updates the Cargo.toml file to include a binary
-fn update_cargo_toml_for_bin(project_dir: &Path, bin_name: &str) -> Result<()> {
-    let cargo_toml_path = project_dir.join("Cargo.toml");
-    let mut cargo_toml = fs::read_to_string(&cargo_toml_path).context("Failed to read Cargo.toml")?;
+impl Manifest {
+    pub fn open(project_dir: &Path) -> Result<Self> {
+        let path = project_dir.join("Cargo.toml");
+        let manifest = fs::read_to_string(&path).context("Failed to read Cargo.toml")?;
+        let document = manifest.parse().context("Failed to parse Cargo.toml")?;
+        Ok(Self { path, document })
+    }
 
-    // Add [[bin]] section for the binary
-    cargo_toml.push_str(&format!("\n[[bin]]\nname = \"{}\"\npath = \"src/bin/{}.rs\"\n", 
-        bin_name, 
-        bin_name
-    ));
+    pub fn save(&self) -> Result<()> {
+        fs::write(&self.path, self.document.to_string()).context("Failed to write updated Cargo.toml")
+    }
 
-    fs::write(cargo_toml_path, cargo_toml).context("Failed to write updated Cargo.toml")?;
+    /// Registers the binary at `path` (relative to the project), named after its file; returns the name
+    pub fn add_bin(&mut self, path: &Path) -> Result<String> {
+        self.add_target("bin", path)
+    }
 
-    Ok(())
+    /// Registers the example at `path` (relative to the project), named after its file; returns the name
+    pub fn add_example(&mut self, path: &Path) -> Result<String> {
+        self.add_target("example", path)
+    }
+
+    // A target already registered for `path` is kept as it is. A name taken by a target with another
+    // path gets a numeric suffix, since cargo rejects two targets of one kind with the same name.
+    fn add_target(&mut self, kind: &str, path: &Path) -> Result<String> {
+        let path = path.to_string_lossy().replace('\\', "/");
+        let stem = Path::new(&path).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let targets = self
+            .document
+            .entry(kind)
+            .or_insert(toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new()))
+            .as_array_of_tables_mut()
+            .with_context(|| format!("`{}` in Cargo.toml is not an array of tables", kind))?;
+
+        let target_name = |target: &toml_edit::Table| target.get("name").and_then(|name| name.as_str()).map(str::to_string);
+        if let Some(existing) = targets
+            .iter()
+            .find(|target| target.get("path").and_then(|p| p.as_str()) == Some(path.as_str()))
+        {
+            return Ok(target_name(existing).unwrap_or(stem));
+        }
+
+        let taken: HashSet<String> = targets.iter().filter_map(target_name).collect();
+        let mut name = stem.clone();
+        for suffix in 2.. {
+            if !taken.contains(&name) {
+                break;
+            }
+            name = format!("{}_{}", stem, suffix);
+        }
+
+        let mut target = toml_edit::Table::new();
+        target.insert("name", toml_edit::value(name.as_str()));
+        target.insert("path", toml_edit::value(path));
+        targets.push(target);
+        Ok(name)
+    }
+
+    /// Adds the crate to `[dependencies]` unless it is listed already, so edits by the learner are kept;
+    /// returns whether it was added
+    pub fn add_dependency(&mut self, dependency: &Dependency) -> Result<bool> {
+        let dependencies = self
+            .document
+            .entry("dependencies")
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .context("`dependencies` in Cargo.toml is not a table")?;
+        if dependencies.contains_key(&dependency.name) {
+            return Ok(false);
+        }
+
+        let spec = if dependency.features.is_empty() {
+            toml_edit::value(dependency.version.as_str())
+        } else {
+            let mut spec = toml_edit::InlineTable::new();
+            spec.insert("version", dependency.version.as_str().into());
+            spec.insert("features", dependency.features.iter().copied().collect::<toml_edit::Array>().into());
+            toml_edit::value(spec)
+        };
+        dependencies.insert(&dependency.name, spec);
+        Ok(true)
+    }
+
+    /// Adds every crate the given source files use, see [`CrateResolver`]
+    pub fn add_dependencies_of<'a>(&mut self, resolver: &CrateResolver, sources: impl IntoIterator<Item = &'a str>) -> Result<()> {
+        for dependency in resolver.resolve(sources) {
+            if self.add_dependency(&dependency)? {
+                tracing::info!("Added dependency {} = \"{}\"", dependency.name, dependency.version);
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges the dependency tables of a generated `Cargo.toml` fragment; crates listed already keep their spec
+    pub fn merge_fragment(&mut self, fragment: &str) -> Result<()> {
+        let fragment: toml_edit::DocumentMut = fragment.parse().context("Failed to parse Cargo.toml fragment")?;
+        for (section, item) in fragment.iter() {
+            if !DEPENDENCY_TABLES.contains(&section) {
+                tracing::warn!("Ignoring [{}] of the generated Cargo.toml", section);
+                continue;
+            }
+            let Some(entries) = item.as_table_like() else {
+                continue;
+            };
+            let merged = self
+                .document
+                .entry(section)
+                .or_insert(toml_edit::table())
+                .as_table_like_mut()
+                .with_context(|| format!("`{}` in Cargo.toml is not a table", section))?;
+            for (name, spec) in entries.iter() {
+                if !merged.contains_key(name) {
+                    merged.insert(name, spec.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets `key` in `[package.metadata.rust-mentor]`, replacing an earlier value
+    pub fn set_metadata(&mut self, key: &str, value: impl Into<toml_edit::Value>) -> Result<()> {
+        let package = self
+            .document
+            .get_mut("package")
+            .and_then(toml_edit::Item::as_table_mut)
+            .context("Cargo.toml has no [package] table")?;
+        let mut implicit = toml_edit::Table::new();
+        implicit.set_implicit(true);
+        let metadata = package
+            .entry("metadata")
+            .or_insert(toml_edit::Item::Table(implicit))
+            .as_table_mut()
+            .context("`package.metadata` in Cargo.toml is not a table")?;
+        let ours = metadata
+            .entry("rust-mentor")
+            .or_insert(toml_edit::table())
+            .as_table_mut()
+            .context("`package.metadata.rust-mentor` in Cargo.toml is not a table")?;
+        ours.insert(key, toml_edit::value(value));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scratch_project;
+
+    #[test]
+    fn new_projects_get_unique_dirs_and_join_the_workspace_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+
+        let first = unique_dir(root, "traits_5_2026-01-01").unwrap();
+        let second = unique_dir(root, "traits_5_2026-01-01").unwrap();
+        assert_eq!(first, root.join("traits_5_2026-01-01"));
+        assert_eq!(second, root.join("traits_5_2026-01-01_2"));
+
+        create_workspace_manifest(root).unwrap();
+        register_workspace_member(root, &first).unwrap();
+        register_workspace_member(root, &second).unwrap();
+        register_workspace_member(root, &first).unwrap();
+        let manifest: toml::Table = toml::from_str(&fs::read_to_string(root.join("Cargo.toml")).unwrap()).unwrap();
+        let members = manifest["workspace"]["members"].as_array().unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1].as_str(), Some("traits_5_2026-01-01_2"));
+    }
+
+    #[test]
+    fn workspace_roots_are_checked_before_a_project_is_created() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let settings = |output_dir: Option<PathBuf>| ProjectSettings {
+            output_dir,
+            workspace: true,
+            git: false,
+        };
+        assert!(create_project_dir("traits_5_2026-01-01", &settings(None)).is_err());
+
+        // A package manifest cannot take members, and no project may be left behind
+        let package_root = root.join("package");
+        fs::create_dir_all(&package_root).unwrap();
+        fs::write(package_root.join("Cargo.toml"), "[package]\nname = \"package\"\n").unwrap();
+        assert!(create_project_dir("traits_5_2026-01-01", &settings(Some(package_root.clone()))).is_err());
+        assert!(!package_root.join("traits_5_2026-01-01").exists());
+
+        // Projects generated before the workspace existed become members
+        let workspace_root = root.join("projects");
+        fs::create_dir_all(workspace_root.join("older_project")).unwrap();
+        fs::write(workspace_root.join("older_project/Cargo.toml"), "[package]\nname = \"older_project\"\n").unwrap();
+        let project = create_project_dir("traits_5_2026-01-01", &settings(Some(workspace_root.clone()))).unwrap();
+        assert_eq!(project, workspace_root.join("traits_5_2026-01-01"));
+        let manifest: toml::Table = toml::from_str(&fs::read_to_string(workspace_root.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(manifest["workspace"]["members"].as_array().unwrap()[0].as_str(), Some("older_project"));
+    }
+
+    #[test]
+    fn manifest_targets_and_dependencies_are_added_once() {
+        assert_eq!(unique_stems(["Borrowing", "BORROWING", "Other"].into_iter()), ["borrowing", "borrowing_2", "other"]);
+
+        let temp = scratch_project("manifest_fixture", "\n[dependencies]\nserde = \"1\"\n", &[]);
+        let dir = temp.path();
+
+        let mut manifest = Manifest::open(dir).unwrap();
+        assert_eq!(manifest.add_bin(Path::new("src/bin/intro.rs")).unwrap(), "intro");
+        assert_eq!(manifest.add_bin(Path::new("src/bin/intro.rs")).unwrap(), "intro");
+        // Same file name in another directory would clash with the first binary
+        assert_eq!(manifest.add_bin(Path::new("src/extra/intro.rs")).unwrap(), "intro_2");
+        assert_eq!(manifest.add_example(Path::new("examples/intro.rs")).unwrap(), "intro");
+        let dependency = |name: &str, version: &str, features| Dependency {
+            name: name.to_string(),
+            version: version.to_string(),
+            features,
+        };
+        assert!(!manifest.add_dependency(&dependency("serde", "1.0.200", &["derive"])).unwrap());
+        assert!(manifest.add_dependency(&dependency("rand", "0.8", &[])).unwrap());
+        assert!(manifest.add_dependency(&dependency("tokio", "1.36", &["full"])).unwrap());
+        manifest.set_metadata("topic", "Ownership").unwrap();
+        manifest.set_metadata("topic", "Borrowing").unwrap();
+        manifest.save().unwrap();
+
+        let saved: toml::Table = toml::from_str(&fs::read_to_string(dir.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(saved["bin"].as_array().unwrap().len(), 2);
+        assert_eq!(saved["example"].as_array().unwrap().len(), 1);
+        assert_eq!(saved["dependencies"]["serde"].as_str(), Some("1"));
+        assert_eq!(saved["dependencies"]["rand"].as_str(), Some("0.8"));
+        assert_eq!(saved["dependencies"]["tokio"]["features"][0].as_str(), Some("full"));
+        assert_eq!(saved["package"]["metadata"]["rust-mentor"]["topic"].as_str(), Some("Borrowing"));
+    }
+
+    #[test]
+    fn application_files_are_written_inside_the_project_only() {
+        let temp = scratch_project("application_fixture", "\n[dependencies]\nrand = \"0.8\"\n", &[]);
+        let dir = temp.path();
+
+        let file = |path: &str, content: &str| GeneratedFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        };
+        let files = [
+            file("src/main.rs", "mod net;\nfn main() { net::serve(rand::random()); }\n"),
+            file("./src/net/mod.rs", "pub fn serve(_port: u16) {}\n"),
+            file("tests/integration.rs", "#[test]\nfn runs() {}\n"),
+            file("../escaped.rs", "fn main() {}\n"),
+            file("/tmp/absolute.rs", "fn main() {}\n"),
+            file(".git/hooks/post-commit", "#!/bin/sh\n"),
+            file("Cargo.toml", "[package]\nname = \"renamed\"\n\n[dependencies]\nrand = \"0.9\"\nregex = \"1\"\n"),
+        ];
+        let mut manifest = Manifest::open(dir).unwrap();
+        write_application_files(dir, &files, &mut manifest, &CrateResolver::load()).unwrap();
+        manifest.save().unwrap();
+
+        assert!(dir.join("src/net/mod.rs").is_file());
+        assert!(dir.join("tests/integration.rs").is_file());
+        assert!(!dir.parent().unwrap().join("escaped.rs").exists());
+        assert!(!dir.join(".git").exists());
+        assert!(project_file_path(dir, "/tmp/absolute.rs").is_err());
+        assert!(project_file_path(dir, ".").is_err());
+
+        let saved: toml::Table = toml::from_str(&fs::read_to_string(dir.join("Cargo.toml")).unwrap()).unwrap();
+        assert_eq!(saved["package"]["name"].as_str(), Some("application_fixture"));
+        assert_eq!(saved["dependencies"]["rand"].as_str(), Some("0.8"));
+        assert_eq!(saved["dependencies"]["regex"].as_str(), Some("1"));
+    }
 }
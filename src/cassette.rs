@@ -0,0 +1,273 @@
+// src/cassette.rs
+use crate::config::{CassetteMode, ProviderSettings};
+use crate::llm_provider::{BoxFuture, Completion, CompletionRequest, LlmProvider, LlmTask, Message, Model};
+use anyhow::Result;
+use chrono::Local;
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Environment variable naming a directory to record LLM traffic into
+pub const RECORD_ENV: &str = "RUST_MENTOR_RECORD";
+/// Environment variable naming a directory to replay recorded LLM traffic from
+pub const REPLAY_ENV: &str = "RUST_MENTOR_REPLAY";
+
+/// One recorded prompt/response pair, stored as a JSON file in the cassette directory
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CassetteEntry {
+    pub task: LlmTask,
+    pub provider: String,
+    pub model: String,
+    pub timestamp: String,
+    pub messages: Vec<Message>,
+    pub response: String,
+}
+
+/// Wraps `provider` in a recorder or replaces it with a replayer, depending on the
+/// environment (which takes precedence) and the configured cassette mode
+pub fn wrap(settings: &ProviderSettings, provider: Arc<dyn LlmProvider>) -> Arc<dyn LlmProvider> {
+    let (mode, dir) = if let Ok(dir) = std::env::var(REPLAY_ENV) {
+        (CassetteMode::Replay, PathBuf::from(dir))
+    } else if let Ok(dir) = std::env::var(RECORD_ENV) {
+        (CassetteMode::Record, PathBuf::from(dir))
+    } else {
+        let dir = settings.cassette_dir.clone().unwrap_or_else(default_dir);
+        (settings.cassette, dir)
+    };
+
+    match mode {
+        CassetteMode::Off => provider,
+        CassetteMode::Record => {
+            tracing::info!("Recording LLM traffic to {}", dir.display());
+            Arc::new(RecordingProvider::new(provider, dir))
+        }
+        CassetteMode::Replay => {
+            tracing::info!("Replaying LLM traffic from {}", dir.display());
+            Arc::new(ReplayProvider::new(dir))
+        }
+    }
+}
+
+// ~/rust-mentor-cassettes, next to the config file
+fn default_dir() -> PathBuf {
+    let user_dirs = UserDirs::new().expect("Could not find user directories");
+    user_dirs.home_dir().join("rust-mentor-cassettes")
+}
+
+// Reads every entry in the cassette directory, in recording order
+fn load_entries(dir: &Path) -> Result<Vec<(PathBuf, CassetteEntry)>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|err| anyhow::anyhow!("Failed to read cassette directory {}: {}", dir.display(), err))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    // File names start with the recording timestamp
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path)?;
+            let entry: CassetteEntry = serde_json::from_str(&content)
+                .map_err(|err| anyhow::anyhow!("Invalid cassette entry {}: {}", path.display(), err))?;
+            Ok((path, entry))
+        })
+        .collect()
+}
+
+/// Forwards requests to the wrapped provider and writes each successful exchange to disk
+pub struct RecordingProvider {
+    inner: Arc<dyn LlmProvider>,
+    dir: PathBuf,
+}
+
+impl RecordingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, dir: PathBuf) -> Self {
+        Self { inner, dir }
+    }
+
+    // A failed write must never cost the user the response, so it is only logged
+    fn record(&self, request: &CompletionRequest, response: &str) {
+        match self.write_entry(request, response) {
+            Ok(path) => tracing::debug!("Recorded {} response to {}", request.task, path.display()),
+            Err(err) => tracing::warn!("Failed to record {} response: {}", request.task, err),
+        }
+    }
+
+    fn write_entry(&self, request: &CompletionRequest, response: &str) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+
+        let now = Local::now();
+        let entry = CassetteEntry {
+            task: request.task,
+            provider: self.inner.name().to_string(),
+            model: request.model.clone(),
+            timestamp: now.to_rfc3339(),
+            messages: request.messages.clone(),
+            response: response.to_string(),
+        };
+
+        let stem = format!("{}_{}", now.format("%Y-%m-%d_%H-%M-%S%.3f"), request.task);
+        let mut path = self.dir.join(format!("{}.json", stem));
+        let mut suffix = 1;
+        while path.exists() {
+            path = self.dir.join(format!("{}_{}.json", stem, suffix));
+            suffix += 1;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&entry)?)?;
+        Ok(path)
+    }
+}
+
+impl LlmProvider for RecordingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let completion = self.inner.complete(request).await?;
+            self.record(request, &completion.content);
+            Ok(completion)
+        })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let completion = self.inner.stream(request, deltas).await?;
+            self.record(request, &completion.content);
+            Ok(completion)
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        self.inner.list_models()
+    }
+}
+
+/// Serves responses from a cassette directory without touching the network.
+/// An entry whose prompt matches exactly is preferred; otherwise entries of the
+/// same task are served in recording order.
+pub struct ReplayProvider {
+    dir: PathBuf,
+    served: Mutex<HashSet<PathBuf>>,
+}
+
+impl ReplayProvider {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            served: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn lookup(&self, request: &CompletionRequest) -> Result<String> {
+        let entries = load_entries(&self.dir)?;
+        let mut served = self.served.lock().unwrap();
+
+        let same_task = || entries.iter().filter(|(_, entry)| entry.task == request.task);
+        let found = same_task()
+            .find(|(path, entry)| !served.contains(path) && entry.messages == request.messages)
+            .or_else(|| same_task().find(|(_, entry)| entry.messages == request.messages))
+            .or_else(|| same_task().find(|(path, _)| !served.contains(path)));
+
+        match found {
+            Some((path, entry)) => {
+                tracing::debug!("Replaying {} response from {}", request.task, path.display());
+                served.insert(path.clone());
+                Ok(entry.response.clone())
+            }
+            None => anyhow::bail!(
+                "No unused cassette entry for a {} prompt in {}",
+                request.task,
+                self.dir.display()
+            ),
+        }
+    }
+}
+
+impl LlmProvider for ReplayProvider {
+    fn name(&self) -> &'static str {
+        "Cassette"
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        // Replayed responses cost nothing, so no usage is reported
+        Box::pin(async move { self.lookup(request).map(Completion::text) })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let content = self.lookup(request)?;
+            for line in content.split_inclusive('\n') {
+                let _ = deltas.send(line.to_string());
+            }
+            Ok(Completion::text(content))
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(async move {
+            let mut models: Vec<Model> = Vec::new();
+            for (_, entry) in load_entries(&self.dir)? {
+                if !models.iter().any(|model| model.id == entry.model) {
+                    models.push(Model {
+                        id: entry.model.clone(),
+                        name: entry.model,
+                        ..Model::default()
+                    });
+                }
+            }
+            Ok(models)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::MockProvider;
+
+    fn fixtures() -> Arc<dyn LlmProvider> {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/llm");
+        Arc::new(MockProvider::new(dir))
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_responses() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let recorder = RecordingProvider::new(fixtures(), dir.to_path_buf());
+        let module = CompletionRequest::new(LlmTask::Module, "test-model".to_string(), "module prompt".to_string());
+        let questions = CompletionRequest::new(LlmTask::Questions, "test-model".to_string(), "quiz prompt".to_string());
+        let recorded_module = recorder.complete(&module).await.unwrap().content;
+        let (deltas, _receiver) = mpsc::unbounded_channel();
+        let recorded_questions = recorder.stream(&questions, deltas).await.unwrap().content;
+
+        let entries = load_entries(dir).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].1.model, "test-model");
+        assert_eq!(entries[0].1.provider, "Mock");
+
+        let replay = ReplayProvider::new(dir.to_path_buf());
+        // Exact prompt matches are found regardless of order
+        assert_eq!(replay.complete(&questions).await.unwrap().content, recorded_questions);
+        // A different prompt falls back to the next unused entry for the task
+        let other = CompletionRequest::new(LlmTask::Module, "test-model".to_string(), "other".to_string());
+        assert_eq!(replay.complete(&other).await.unwrap().content, recorded_module);
+        assert!(replay.complete(&other).await.is_err());
+    }
+}
@@ -0,0 +1,267 @@
+// src/compile_check.rs
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+// Generous enough for a cold `cargo check` of a project without dependencies
+const CHECK_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A compiler error or warning attributed to one file of the project
+#[derive(Debug, Clone)]
+pub struct CompilerDiagnostic {
+    pub message: String,
+    // Line of the primary span, when the compiler reported one
+    pub line: Option<usize>,
+    // Full rustc output with code excerpt, as printed on the terminal
+    pub rendered: String,
+}
+
+/// Compile result of one example or exercise file
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    // Relative to the project directory, e.g. `examples/moving_a_string.rs`
+    pub path: PathBuf,
+    // False when cargo never got to the file, e.g. because the manifest is invalid
+    pub checked: bool,
+    pub errors: Vec<CompilerDiagnostic>,
+    pub warnings: Vec<CompilerDiagnostic>,
+}
+
+impl FileReport {
+    pub fn passed(&self) -> bool {
+        self.checked && self.errors.is_empty()
+    }
+
+    pub fn failed(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Exercises are binaries under `src/bin`; they may be meant not to compile yet
+    pub fn is_exercise(&self) -> bool {
+        self.path.starts_with("src/bin")
+    }
+}
+
+/// Outcome of `cargo check` for a generated project
+#[derive(Debug, Clone, Default)]
+pub struct CompileReport {
+    pub files: Vec<FileReport>,
+    // Errors that could not be attributed to an example or exercise, e.g. a broken Cargo.toml
+    pub project_errors: Vec<String>,
+}
+
+impl CompileReport {
+    pub fn file(&self, path: &Path) -> Option<&FileReport> {
+        self.files.iter().find(|file| file.path == path)
+    }
+}
+
+// The parts of cargo's `--message-format=json` output that are needed
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    #[serde(default)]
+    target: Option<CargoTarget>,
+    #[serde(default)]
+    message: Option<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct CargoTarget {
+    src_path: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct RustcMessage {
+    message: String,
+    level: String,
+    #[serde(default)]
+    rendered: Option<String>,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    line_start: usize,
+    is_primary: bool,
+}
+
+// Source file of a target cargo finished checking; targets with errors produce no artifact
+fn checked_src_path(line: &str) -> Option<PathBuf> {
+    let message = serde_json::from_str::<CargoMessage>(line).ok()?;
+    if message.reason != "compiler-artifact" {
+        return None;
+    }
+    Some(message.target?.src_path)
+}
+
+/// A rustc message from cargo's JSON output, with the source file of the target it belongs to
+pub struct CompilerMessage {
+    pub src_path: PathBuf,
+    pub level: String,
+    pub diagnostic: CompilerDiagnostic,
+}
+
+/// Parses one line of `--message-format=json` output; anything but a rustc message with a span is skipped
+pub fn compiler_message(line: &str) -> Option<CompilerMessage> {
+    let message = serde_json::from_str::<CargoMessage>(line).ok()?;
+    if message.reason != "compiler-message" {
+        return None;
+    }
+    let (target, rustc) = (message.target?, message.message?);
+    // "aborting due to N previous errors" and similar summaries have no span
+    if rustc.spans.is_empty() {
+        return None;
+    }
+
+    Some(CompilerMessage {
+        src_path: target.src_path,
+        level: rustc.level,
+        diagnostic: CompilerDiagnostic {
+            line: rustc.spans.iter().find(|span| span.is_primary).map(|span| span.line_start),
+            rendered: rustc.rendered.unwrap_or_else(|| rustc.message.clone()),
+            message: rustc.message,
+        },
+    })
+}
+
+/// Runs `cargo check` on every example and binary of the project at `project_dir`
+pub async fn check_project(project_dir: &Path) -> Result<CompileReport> {
+    let project_dir = project_dir
+        .canonicalize()
+        .with_context(|| format!("Project directory {} does not exist", project_dir.display()))?;
+
+    let command = Command::new("cargo")
+        .current_dir(&project_dir)
+        .args(["check", "--offline", "--message-format=json", "--keep-going", "--bins", "--examples"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(CHECK_TIMEOUT, command)
+        .await
+        .map_err(|_| anyhow::anyhow!("cargo check did not finish within {}s", CHECK_TIMEOUT.as_secs()))?
+        .context("Failed to run cargo check")?;
+
+    let mut report = CompileReport {
+        files: source_files(&project_dir)
+            .into_iter()
+            .map(|path| FileReport {
+                path,
+                checked: false,
+                errors: Vec::new(),
+                warnings: Vec::new(),
+            })
+            .collect(),
+        project_errors: Vec::new(),
+    };
+
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(src_path) = checked_src_path(line) {
+            let path = src_path.strip_prefix(&project_dir).unwrap_or(&src_path);
+            if let Some(file) = report.files.iter_mut().find(|file| file.path == path) {
+                file.checked = true;
+            }
+            continue;
+        }
+        let Some(message) = compiler_message(line) else {
+            continue;
+        };
+        let path = message.src_path.strip_prefix(&project_dir).unwrap_or(&message.src_path).to_path_buf();
+        let Some(file) = report.files.iter_mut().find(|file| file.path == path) else {
+            if message.level == "error" {
+                report.project_errors.push(format!("{}: {}", path.display(), message.diagnostic.message));
+            }
+            continue;
+        };
+        file.checked = true;
+        match message.level.as_str() {
+            "error" => file.errors.push(message.diagnostic),
+            "warning" => file.warnings.push(message.diagnostic),
+            _ => {}
+        }
+    }
+
+    // cargo itself failed (e.g. an invalid manifest) without any rustc message to show for it
+    if !output.status.success()
+        && !report.files.iter().any(FileReport::failed)
+        && report.project_errors.is_empty()
+    {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        report
+            .project_errors
+            .push(stderr.lines().filter(|line| line.contains("error")).collect::<Vec<_>>().join("\n"));
+    }
+
+    Ok(report)
+}
+
+// Examples and exercise binaries of the project, relative to its directory, in a stable order
+fn source_files(project_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in ["examples", "src/bin"] {
+        let Ok(entries) = std::fs::read_dir(project_dir.join(dir)) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+            .filter_map(|path| path.strip_prefix(project_dir).ok().map(Path::to_path_buf))
+            .collect();
+        paths.sort();
+        files.append(&mut paths);
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scratch_project;
+
+    #[tokio::test]
+    async fn attributes_errors_to_the_failing_file() {
+        let temp = scratch_project(
+            "check_fixture",
+            "",
+            &[
+                ("src/main.rs", "fn main() {}\n"),
+                ("examples/good.rs", "fn main() {\n    let unused = 1;\n}\n"),
+                ("src/bin/bad.rs", "fn main() {\n    let x: u32 = \"no\";\n}\n"),
+            ],
+        );
+        let dir = temp.path();
+
+        let report = check_project(dir).await.unwrap();
+        let good = report.file(Path::new("examples/good.rs")).unwrap();
+        assert!(good.passed());
+        assert_eq!(good.warnings.len(), 1);
+
+        let bad = report.file(Path::new("src/bin/bad.rs")).unwrap();
+        assert!(!bad.passed());
+        assert!(bad.is_exercise());
+        assert_eq!(bad.errors[0].line, Some(2));
+        assert!(bad.errors[0].rendered.contains("mismatched types"));
+    }
+
+    #[tokio::test]
+    async fn files_are_not_checked_when_cargo_fails_first() {
+        let temp = scratch_project(
+            "unchecked_fixture",
+            "\n[dependencies]\nno-such-crate-rust-mentor = \"1\"\n",
+            &[("examples/intro.rs", "fn main() {}\n")],
+        );
+        let dir = temp.path();
+
+        let report = check_project(dir).await.unwrap();
+        let intro = report.file(Path::new("examples/intro.rs")).unwrap();
+        assert!(!intro.checked);
+        assert!(!intro.passed());
+        assert!(!intro.failed());
+        assert!(!report.project_errors.is_empty());
+    }
+}
@@ -2,16 +2,222 @@ use directories::UserDirs;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Read;
-use toml_edit;
+use std::path::PathBuf;
 use crate::app::LearningGoal;
+use crate::llm_provider::{LlmTask, SamplingOptions};
 use crate::question_generator::QuestionType;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Config {
     pub model: String,
+    // Models tried in order when the primary model fails or returns an unusable module
+    #[serde(default)]
+    pub fallback_models: Vec<String>,
+    // Stop making LLM calls once the lifetime spend recorded in ~/rust-mentor-usage.jsonl reaches this amount
+    #[serde(default)]
+    pub spend_budget_usd: Option<f64>,
+    // How learning modules are requested: `<<<...>>>` delimited text or a JSON object
+    #[serde(default)]
+    pub module_format: ModuleFormat,
+    // Times a malformed module is sent back to the model with a list of its problems before giving up
+    #[serde(default = "default_module_repair_attempts")]
+    pub module_repair_attempts: u32,
+    // Rounds of sending `cargo check` errors of generated snippets back to the model for a fix
+    #[serde(default = "default_compile_fix_attempts")]
+    pub compile_fix_attempts: u32,
+    // Limit for running a snippet or the tests of an exercise, including the time cargo needs to build it
+    #[serde(default = "default_snippet_run_timeout_secs")]
+    pub snippet_run_timeout_secs: u64,
     pub learning_resources: LearningResources,
     pub content_customization: ContentCustomization,
     pub question_generator_settings: QuestionGeneratorSettings,
+    #[serde(default)]
+    pub provider: ProviderSettings,
+    #[serde(default)]
+    pub tasks: TaskSettings,
+    #[serde(default)]
+    pub projects: ProjectSettings,
+}
+
+// Per-task overrides, e.g. a cheap model for quizzes and a strong one for applications
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TaskSettings {
+    pub module: TaskProfile,
+    pub questions: TaskProfile,
+    pub application: TaskProfile,
+    pub snippet: TaskProfile,
+}
+
+impl TaskSettings {
+    pub fn profile(&self, task: LlmTask) -> &TaskProfile {
+        match task {
+            LlmTask::Module => &self.module,
+            LlmTask::Questions => &self.questions,
+            LlmTask::Application => &self.application,
+            LlmTask::Snippet => &self.snippet,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct TaskProfile {
+    // Tried before the global model chain
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    // Sent as a system message ahead of the prompt
+    pub system_prompt: Option<String>,
+}
+
+impl TaskProfile {
+    pub fn sampling(&self) -> SamplingOptions {
+        SamplingOptions {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+        }
+    }
+}
+
+fn default_module_repair_attempts() -> u32 {
+    2
+}
+
+fn default_compile_fix_attempts() -> u32 {
+    2
+}
+
+fn default_snippet_run_timeout_secs() -> u64 {
+    60
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum ModuleFormat {
+    #[default]
+    Delimited,
+    // Asks for JSON matching the module schema, enforced through `response_format` where supported
+    Json,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub enum ProviderKind {
+    #[default]
+    OpenRouter,
+    OpenAiCompatible,
+    Ollama,
+    Mock,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum CassetteMode {
+    #[default]
+    Off,
+    Record,
+    Replay,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ProviderSettings {
+    pub kind: ProviderKind,
+    // Stream learning modules token by token instead of waiting for the full response
+    pub stream: bool,
+    // Ask OpenAI-compatible servers for token usage at the end of a stream (`stream_options.include_usage`);
+    // older servers reject the option
+    pub stream_usage: bool,
+    // Overrides the provider's default endpoint, e.g. "http://10.0.0.5:8080/v1"
+    pub base_url: Option<String>,
+    // Environment variable holding the API key, overriding the provider's default
+    pub api_key_env: Option<String>,
+    // Directory with module.txt, questions.txt and application.txt used by the Mock provider
+    pub fixtures_dir: Option<PathBuf>,
+    // Record every prompt/response pair to `cassette_dir`, or serve responses from it
+    pub cassette: CassetteMode,
+    pub cassette_dir: Option<PathBuf>,
+    pub retry: RetrySettings,
+}
+
+// Where generated Cargo projects go
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ProjectSettings {
+    // Parent directory of generated projects; the current directory when unset
+    pub output_dir: Option<PathBuf>,
+    // Make `output_dir` a Cargo workspace with every generated project registered as a member
+    pub workspace: bool,
+    // Give every project its own git repository and commit whatever the app generates or rewrites
+    pub git: bool,
+}
+
+impl Default for ProjectSettings {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            workspace: false,
+            git: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct RetrySettings {
+    // Seconds to wait for a response, or between streamed chunks, before giving up on an attempt
+    pub timeout_secs: u64,
+    // Total attempts per call, including the first one
+    pub max_attempts: u32,
+    // Backoff before the first retry, doubled for every further attempt up to `max_backoff_ms`
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 120,
+            max_attempts: 4,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl Default for ProviderSettings {
+    fn default() -> Self {
+        Self {
+            kind: ProviderKind::default(),
+            stream: true,
+            stream_usage: true,
+            base_url: None,
+            api_key_env: None,
+            fixtures_dir: None,
+            cassette: CassetteMode::default(),
+            cassette_dir: None,
+            retry: RetrySettings::default(),
+        }
+    }
+}
+
+impl ProviderSettings {
+    /// Whether the provider reports what each call cost, which `spend_budget_usd` depends on
+    pub fn reports_cost(&self) -> bool {
+        self.kind == ProviderKind::OpenRouter
+    }
+
+    /// Name of the environment variable the API key is read from, if the provider uses one
+    pub fn api_key_var(&self) -> Option<&str> {
+        if let Some(var) = &self.api_key_env {
+            return Some(var);
+        }
+        match self.kind {
+            ProviderKind::OpenRouter => Some("OPENROUTER_API_KEY"),
+            ProviderKind::OpenAiCompatible => Some("OPENAI_API_KEY"),
+            ProviderKind::Ollama | ProviderKind::Mock => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -61,6 +267,40 @@ pub struct QuestionGeneratorSettings {
     pub enable_application_generation: bool,
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            model: "google/gemma-3n-e4b-it:free".to_string(),
+            fallback_models: Vec::new(),
+            spend_budget_usd: None,
+            module_format: ModuleFormat::default(),
+            module_repair_attempts: default_module_repair_attempts(),
+            compile_fix_attempts: default_compile_fix_attempts(),
+            snippet_run_timeout_secs: default_snippet_run_timeout_secs(),
+            learning_resources: LearningResources {
+                show_official_docs: true,
+                show_community_resources: true,
+                show_crates_io: true,
+                show_github_repos: true,
+            },
+            content_customization: ContentCustomization {
+                code_complexity: CodeComplexity::Moderate,
+                explanation_verbosity: ExplanationVerbosity::Moderate,
+                focus_area: FocusArea::Balanced,
+                learning_goal: LearningGoal::General,
+            },
+            question_generator_settings: QuestionGeneratorSettings {
+                num_questions: 5,
+                default_question_type: QuestionType::Multiple,
+                enable_application_generation: true,
+            },
+            provider: ProviderSettings::default(),
+            tasks: TaskSettings::default(),
+            projects: ProjectSettings::default(),
+        }
+    }
+}
+
 impl Config {
     pub fn load() -> Result<Config, Box<dyn std::error::Error>> {
         let user_dirs = UserDirs::new().expect("Could not find user directories");
@@ -72,26 +312,7 @@ impl Config {
             config_file.read_to_string(&mut config_string)?;
             toml_edit::de::from_str(&config_string)?
         } else {
-            let default_config = Config {
-                model: "google/gemma-3n-e4b-it:free".to_string(),
-                learning_resources: LearningResources {
-                    show_official_docs: true,
-                    show_community_resources: true,
-                    show_crates_io: true,
-                    show_github_repos: true,
-                },
-                content_customization: ContentCustomization {
-                    code_complexity: CodeComplexity::Moderate,
-                    explanation_verbosity: ExplanationVerbosity::Moderate,
-                    focus_area: FocusArea::Balanced,
-                    learning_goal: LearningGoal::General,
-                },
-                question_generator_settings: QuestionGeneratorSettings {
-                    num_questions: 5,
-                    default_question_type: QuestionType::Multiple,
-                    enable_application_generation: true,
-                },
-            };
+            let default_config = Config::default();
             let toml = toml::to_string(&default_config)?;
             fs::write(&config_path, toml)?;
             default_config
@@ -100,6 +321,19 @@ impl Config {
         Ok(config)
     }
 
+    /// Models to try for `task`: its own model if configured, then the primary
+    /// model and the fallback models, without duplicates
+    pub fn model_chain(&self, task: LlmTask) -> Vec<String> {
+        let task_model = self.tasks.profile(task).model.iter();
+        let mut chain = Vec::new();
+        for model in task_model.chain(std::iter::once(&self.model)).chain(&self.fallback_models) {
+            if !chain.contains(model) {
+                chain.push(model.clone());
+            }
+        }
+        chain
+    }
+
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
         let user_dirs = UserDirs::new().expect("Could not find user directories");
         let config_path = user_dirs.home_dir().join("rust-mentor.conf");
@@ -111,6 +345,8 @@ impl Config {
 
 pub struct ConfigService {
     config: Config,
+    // False for a config that only lives in memory, whose changes are never written to ~/rust-mentor.conf
+    persist: bool,
 }
 
 
@@ -120,7 +356,7 @@ impl ConfigService {
         match config {
             Ok(config) => {
                 tracing::info!("Loaded config: {:?}", config);
-                ConfigService { config }
+                ConfigService { config, persist: true }
             },
             Err(_) => {
                 tracing::error!("Failed to load config (~/rust-mentor.conf) - delete config file and rerun.");
@@ -129,13 +365,26 @@ impl ConfigService {
         }
     }
 
+    /// A service for `config` that neither reads nor writes ~/rust-mentor.conf, e.g. for tests
+    #[cfg(test)]
+    pub fn in_memory(config: Config) -> Self {
+        ConfigService { config, persist: false }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.persist {
+            self.config.save()?;
+        }
+        Ok(())
+    }
+
     pub fn get_config(&self) -> &Config {
         &self.config
     }
 
     pub fn update_model(&mut self, model: String) -> Result<(), Box<dyn std::error::Error>> {
         self.config.model = model;
-        self.config.save()
+        self.save()
     }
 
     // Learning resources methods
@@ -143,29 +392,24 @@ impl ConfigService {
         &self.config.learning_resources
     }
 
-    pub fn update_learning_resources(&mut self, resources: LearningResources) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.learning_resources = resources;
-        self.config.save()
-    }
-
     pub fn toggle_official_docs(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.config.learning_resources.show_official_docs = !self.config.learning_resources.show_official_docs;
-        self.config.save()
+        self.save()
     }
 
     pub fn toggle_community_resources(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.config.learning_resources.show_community_resources = !self.config.learning_resources.show_community_resources;
-        self.config.save()
+        self.save()
     }
 
     pub fn toggle_crates_io(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.config.learning_resources.show_crates_io = !self.config.learning_resources.show_crates_io;
-        self.config.save()
+        self.save()
     }
 
     pub fn toggle_github_repos(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.config.learning_resources.show_github_repos = !self.config.learning_resources.show_github_repos;
-        self.config.save()
+        self.save()
     }
 
     // Content customization methods
@@ -173,26 +417,6 @@ impl ConfigService {
         &self.config.content_customization
     }
 
-    pub fn update_content_customization(&mut self, customization: ContentCustomization) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.content_customization = customization;
-        self.config.save()
-    }
-
-    pub fn update_code_complexity(&mut self, complexity: CodeComplexity) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.content_customization.code_complexity = complexity;
-        self.config.save()
-    }
-
-    pub fn update_explanation_verbosity(&mut self, verbosity: ExplanationVerbosity) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.content_customization.explanation_verbosity = verbosity;
-        self.config.save()
-    }
-
-    pub fn update_focus_area(&mut self, focus_area: FocusArea) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.content_customization.focus_area = focus_area;
-        self.config.save()
-    }
-
     pub fn increment_num_questions(&mut self) {
         if self.config.question_generator_settings.num_questions < MAX_QUESTIONS {
             self.config.question_generator_settings.num_questions += 1;
@@ -210,7 +434,7 @@ impl ConfigService {
             CodeComplexity::Moderate => CodeComplexity::Complex,
             CodeComplexity::Complex => CodeComplexity::Simple,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_code_complexity_reverse(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -219,7 +443,7 @@ impl ConfigService {
             CodeComplexity::Moderate => CodeComplexity::Simple,
             CodeComplexity::Complex => CodeComplexity::Moderate,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_explanation_verbosity(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -228,7 +452,7 @@ impl ConfigService {
             ExplanationVerbosity::Moderate => ExplanationVerbosity::Detailed,
             ExplanationVerbosity::Detailed => ExplanationVerbosity::Concise,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_explanation_verbosity_reverse(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -237,7 +461,7 @@ impl ConfigService {
             ExplanationVerbosity::Moderate => ExplanationVerbosity::Concise,
             ExplanationVerbosity::Detailed => ExplanationVerbosity::Moderate,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_focus_area(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -247,7 +471,7 @@ impl ConfigService {
             FocusArea::Exercises => FocusArea::Balanced,
             FocusArea::Balanced => FocusArea::Concepts,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_focus_area_reverse(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -257,7 +481,7 @@ impl ConfigService {
             FocusArea::Exercises => FocusArea::CodeExamples,
             FocusArea::Balanced => FocusArea::Exercises,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_learning_goal(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -307,7 +531,7 @@ impl ConfigService {
             LearningGoal::UserInterface => LearningGoal::WebDevelopment,
             LearningGoal::WebDevelopment => LearningGoal::ARVR,
         };
-        self.config.save()
+        self.save()
     }
 
     pub fn cycle_learning_goal_reverse(&mut self) -> Result<(), Box<dyn std::error::Error>> {
@@ -357,7 +581,7 @@ impl ConfigService {
             LearningGoal::UserInterface => LearningGoal::Transformers,
             LearningGoal::WebDevelopment => LearningGoal::UserInterface,
         };
-        self.config.save()
+        self.save()
     }
 
     // Question generator settings methods
@@ -365,31 +589,11 @@ impl ConfigService {
         &self.config.question_generator_settings
     }
 
-    pub fn update_question_generator_settings(&mut self, settings: QuestionGeneratorSettings) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.question_generator_settings = settings;
-        self.config.save()
-    }
-
-    pub fn update_num_questions(&mut self, num_questions: usize) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.question_generator_settings.num_questions = num_questions;
-        self.config.save()
-    }
-
-    pub fn update_default_question_type(&mut self, question_type: QuestionType) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.question_generator_settings.default_question_type = question_type;
-        self.config.save()
-    }
-
-    pub fn toggle_application_generation(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        self.config.question_generator_settings.enable_application_generation = !self.config.question_generator_settings.enable_application_generation;
-        self.config.save()
-    }
-
     pub fn cycle_question_type(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.config.question_generator_settings.default_question_type = match self.config.question_generator_settings.default_question_type {
             QuestionType::Binary => QuestionType::Multiple,
             QuestionType::Multiple => QuestionType::Binary,
         };
-        self.config.save()
+        self.save()
     }
 }
@@ -0,0 +1,303 @@
+// src/dependencies.rs
+use crate::data;
+use directories::UserDirs;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+// Paths that never name an external crate
+const BUILTIN_ROOTS: &[&str] = &["std", "core", "alloc", "crate", "self", "super", "Self", "proc_macro"];
+
+// Features generated code relies on without saying so, e.g. `#[derive(Serialize)]` or `#[tokio::main]`
+const DEFAULT_FEATURES: &[(&str, &[&str])] = &[("serde", &["derive"]), ("tokio", &["full"]), ("clap", &["derive"])];
+
+/// An external crate used by generated code, ready to go into `[dependencies]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    pub features: &'static [&'static str],
+}
+
+/// Maps the crate names found in code to the crates the local Cargo registry cache has a copy of,
+/// which also supplies the versions. Projects are built with `--offline`, so other crates are left out
+/// and listed by [`CrateResolver::missing`]. Loading scans the cache, so load it once per project.
+pub struct CrateResolver {
+    // Crate names from `data/rust_library_index.json`, to point out well-known crates that are not cached
+    index: HashSet<String>,
+    // Newest cached release of every crate in the registry cache
+    registry: HashMap<String, String>,
+    // Well-known crates used by the code resolved so far that have no cached copy
+    missing: Mutex<BTreeSet<String>>,
+}
+
+impl CrateResolver {
+    pub fn load() -> Self {
+        let index = match data::load_rust_library_index() {
+            Ok(topics) => topics.into_iter().map(|topic| topic.library_name).collect(),
+            Err(err) => {
+                tracing::warn!("Failed to load the library index, only cached crates are recognised: {}", err);
+                Vec::new()
+            }
+        };
+        let cache_dirs = cargo_home()
+            .and_then(|home| fs::read_dir(home.join("registry").join("cache")).ok())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()));
+        Self::new(index, cache_dirs)
+    }
+
+    fn new(index: impl IntoIterator<Item = String>, cache_dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut registry: HashMap<String, String> = HashMap::new();
+        for dir in cache_dirs {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let Some((name, version)) = file_name.strip_suffix(".crate").and_then(split_crate_file) else {
+                    continue;
+                };
+                let newer = registry.get(name).is_none_or(|cached| version_key(version) > version_key(cached));
+                if newer {
+                    registry.insert(name.to_string(), version.to_string());
+                }
+            }
+        }
+        Self {
+            index: index.into_iter().collect(),
+            registry,
+            missing: Mutex::default(),
+        }
+    }
+
+    /// Crates the resolved code needs that could not be added, for the learner to add by hand
+    pub fn missing(&self) -> Vec<String> {
+        self.missing.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Dependencies needed by the given source files, sorted by name
+    pub fn resolve<'a>(&self, sources: impl IntoIterator<Item = &'a str>) -> Vec<Dependency> {
+        let mut roots = BTreeSet::new();
+        let mut local_modules = HashSet::new();
+        for source in sources {
+            let (used, modules) = crate_roots(source);
+            roots.extend(used);
+            local_modules.extend(modules);
+        }
+
+        roots
+            .into_iter()
+            .filter(|root| !local_modules.contains(root))
+            .filter_map(|root| self.crate_for(&root))
+            .map(|(name, version)| Dependency {
+                version,
+                features: DEFAULT_FEATURES
+                    .iter()
+                    .find(|(crate_name, _)| *crate_name == name)
+                    .map_or(&[], |(_, features)| features),
+                name,
+            })
+            .collect()
+    }
+
+    // Crate name and cached version for a path root; `use` paths spell crates with underscores,
+    // so `actix_web` may be the `actix-web` crate
+    fn crate_for(&self, root: &str) -> Option<(String, String)> {
+        let hyphenated = root.replace('_', "-");
+        let names = [root, hyphenated.as_str()];
+        if let Some((name, version)) = names.iter().find_map(|name| self.registry.get_key_value(*name)) {
+            return Some((name.clone(), version.clone()));
+        }
+        if let Some(name) = names.iter().find(|name| self.index.contains(**name)) {
+            tracing::warn!("Generated code uses `{}`, which is not in the local registry cache; add it to Cargo.toml by hand", name);
+            self.missing.lock().unwrap().insert(name.to_string());
+        }
+        None
+    }
+}
+
+// First segments of the paths in `code` that may name a crate (`serde` in `use serde::Deserialize`,
+// `rand` in `rand::random()` or `extern crate rand;`), and the modules `code` declares itself
+fn crate_roots(code: &str) -> (BTreeSet<String>, HashSet<String>) {
+    let mut found = Roots::default();
+    // Tokenizing skips comments and string literals, and works for snippets that are not a whole file
+    if let Ok(tokens) = code.parse::<TokenStream>() {
+        collect_roots(tokens, &mut found);
+    }
+    // A path like `thread::sleep` starts at a name brought into scope by `use std::thread;`, not at a crate
+    let mut roots = found.roots;
+    roots.extend(found.path_starts.into_iter().filter(|start| !found.imported.contains(start)));
+    roots.retain(|root| !BUILTIN_ROOTS.contains(&root.as_str()));
+    (roots, found.modules)
+}
+
+#[derive(Default)]
+struct Roots {
+    // Crates named by `use` and `extern crate` items
+    roots: BTreeSet<String>,
+    // First segments of the other paths, which may also start at an imported name
+    path_starts: BTreeSet<String>,
+    // Every name that appears in a `use` item
+    imported: HashSet<String>,
+    modules: HashSet<String>,
+}
+
+fn collect_roots(tokens: TokenStream, found: &mut Roots) {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let is_punct = |index: usize, ch: char| matches!(tokens.get(index), Some(TokenTree::Punct(punct)) if punct.as_char() == ch);
+    let ident_at = |index: usize| match tokens.get(index) {
+        Some(TokenTree::Ident(ident)) => Some(ident.to_string()),
+        _ => None,
+    };
+
+    let mut index = 0;
+    while index < tokens.len() {
+        match &tokens[index] {
+            TokenTree::Group(group) => collect_roots(group.stream(), found),
+            TokenTree::Ident(ident) if ident == "use" => {
+                let end = (index..tokens.len()).find(|&end| is_punct(end, ';')).unwrap_or(tokens.len());
+                collect_use_tree(&tokens[index + 1..end], found);
+                index = end;
+            }
+            TokenTree::Ident(ident) => {
+                let starts_path = is_punct(index + 1, ':') && is_punct(index + 2, ':');
+                let inside_path = index >= 1 && is_punct(index - 1, ':');
+                if starts_path && !inside_path {
+                    found.path_starts.insert(ident.to_string());
+                }
+                if let Some(next) = ident_at(index + 1) {
+                    if ident == "mod" {
+                        found.modules.insert(next);
+                    } else if ident == "crate" && index >= 1 && ident_at(index - 1).as_deref() == Some("extern") {
+                        found.roots.insert(next);
+                    }
+                }
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+}
+
+// Only the first segment of a `use` tree can be a crate: in `use std::{thread, time::Duration}`,
+// `time` is `std::time`. A leading brace group (`use {rand, serde::Serialize};`) lists several trees.
+fn collect_use_tree(tree: &[TokenTree], found: &mut Roots) {
+    let start = tree.iter().position(|token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ':'));
+    match start.map(|start| &tree[start]) {
+        Some(TokenTree::Ident(root)) => {
+            found.roots.insert(root.to_string());
+        }
+        Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Brace => {
+            let items: Vec<TokenTree> = group.stream().into_iter().collect();
+            for item in items.split(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ',')) {
+                collect_use_tree(item, found);
+            }
+        }
+        _ => {}
+    }
+    collect_imported(tree.iter().cloned().collect(), &mut found.imported);
+}
+
+fn collect_imported(tokens: TokenStream, imported: &mut HashSet<String>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                imported.insert(ident.to_string());
+            }
+            TokenTree::Group(group) => collect_imported(group.stream(), imported),
+            _ => {}
+        }
+    }
+}
+
+// `serde_json-1.0.140` -> (`serde_json`, `1.0.140`); crate names may contain `-` but never start a part with a digit
+fn split_crate_file(file_stem: &str) -> Option<(&str, &str)> {
+    let (index, _) = file_stem
+        .match_indices('-')
+        .find(|(index, _)| file_stem[index + 1..].starts_with(|c: char| c.is_ascii_digit()))?;
+    Some((&file_stem[..index], &file_stem[index + 1..]))
+}
+
+// Orders releases numerically; pre-releases sort below everything so they are only used when nothing else is cached
+fn version_key(version: &str) -> (bool, Vec<u64>) {
+    let (release, pre) = version.split_once('-').map_or((version, None), |(release, pre)| (release, Some(pre)));
+    (pre.is_none(), release.split('.').map(|part| part.parse().unwrap_or(0)).collect())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| UserDirs::new().map(|dirs| dirs.home_dir().join(".cargo")))
+        .filter(|home| Path::new(home).is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_crates_in_paths_and_resolves_cached_versions() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = temp.path();
+        for file in ["serde-1.0.9.crate", "serde-1.0.219.crate", "serde-2.0.0-rc.1.crate", "actix-web-4.4.0.crate", "tokio-1.36.0.crate", "net-0.1.0.crate"] {
+            fs::write(cache.join(file), "").unwrap();
+        }
+        let resolver = CrateResolver::new(
+            ["serde", "tokio", "actix-web", "rand"].map(str::to_string),
+            [cache.to_path_buf()],
+        );
+
+        let snippet = r#"
+            use serde::{Deserialize, Serialize};
+            use std::collections::HashMap;
+            use actix_web::App;
+            mod net;
+            use net::Client;
+
+            // tokio::spawn only appears in a comment
+            #[tokio::main]
+            async fn main() {
+                let n: u8 = rand::random();
+                println!("regex::Regex is just text: {}", n);
+                Vec::<u8>::new();
+            }
+        "#;
+        let dependencies = resolver.resolve([snippet]);
+        let names: Vec<&str> = dependencies.iter().map(|dependency| dependency.name.as_str()).collect();
+        // rand is a known crate, but without a cached copy it could not be built offline
+        assert_eq!(names, ["actix-web", "serde", "tokio"]);
+        assert_eq!(resolver.missing(), ["rand"]);
+
+        assert_eq!(dependencies[0].version, "4.4.0");
+        assert_eq!(dependencies[1].version, "1.0.219");
+        assert_eq!(dependencies[1].features, ["derive"]);
+        assert_eq!(dependencies[2].version, "1.36.0");
+        assert_eq!(dependencies[2].features, ["full"]);
+    }
+
+    #[test]
+    fn names_inside_std_use_groups_are_not_crates() {
+        let temp = tempfile::tempdir().unwrap();
+        for file in ["time-0.3.36.crate", "atomic-0.6.0.crate", "thread-0.1.0.crate", "rand-0.8.5.crate"] {
+            fs::write(temp.path().join(file), "").unwrap();
+        }
+        let resolver = CrateResolver::new([], [temp.path().to_path_buf()]);
+
+        let snippet = r#"
+            use std::{thread, time::Duration};
+            use std::sync::{atomic::AtomicUsize, Arc};
+            use {rand::Rng, std::fmt};
+
+            fn main() {
+                let counter = Arc::new(AtomicUsize::new(0));
+                thread::sleep(Duration::from_millis(1));
+            }
+        "#;
+        let names: Vec<String> = resolver.resolve([snippet]).into_iter().map(|dependency| dependency.name).collect();
+        assert_eq!(names, ["rand"]);
+    }
+}
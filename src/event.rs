@@ -11,28 +11,26 @@ pub enum Event {
 }
 
 pub struct EventHandler {
-    sender: mpsc::Sender<Event>,
     receiver: mpsc::Receiver<Event>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate_ms: u64) -> Self {
-        let (sender, receiver) = mpsc::channel(100);
+        let (event_sender, receiver) = mpsc::channel(100);
         let tick_rate = Duration::from_millis(tick_rate_ms);
-        let event_sender = sender.clone();
 
         tokio::spawn(async move {
             loop {
-                if event::poll(tick_rate).unwrap_or(false) {
-                    if let Ok(CrosstermEvent::Key(key)) = event::read() {
-                        event_sender.send(Event::Key(key)).await.ok();
-                    }
+                if event::poll(tick_rate).unwrap_or(false)
+                    && let Ok(CrosstermEvent::Key(key)) = event::read()
+                {
+                    event_sender.send(Event::Key(key)).await.ok();
                 }
                 event_sender.send(Event::Tick).await.ok();
             }
         });
 
-        Self { sender, receiver }
+        Self { receiver }
     }
 
     pub async fn next(&mut self) -> Result<Event> {
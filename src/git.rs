@@ -0,0 +1,84 @@
+// src/git.rs
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Commits made by the app, so the learner's own commits and edits stand apart from generated content
+const AUTHOR: [&str; 6] = ["-c", "user.name=RustMentor", "-c", "user.email=rust-mentor@localhost", "-c", "commit.gpgsign=false"];
+
+/// Creates a repository in `dir` unless it has its own already; `cargo init` skips this inside another repository
+pub fn init(dir: &Path) -> Result<()> {
+    if !dir.join(".git").exists() {
+        run(git(dir).args(["init", "--quiet"]))?;
+    }
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(ignore, "/target\n").context("Failed to write .gitignore")?;
+    }
+    Ok(())
+}
+
+/// Commits the current state of `paths` (relative to `dir`; `.` for everything) and nothing else, so edits
+/// of other files stay uncommitted. Returns false when they did not change since the last commit.
+pub fn commit(dir: &Path, paths: &[&Path], message: &str) -> Result<bool> {
+    run(git(dir).args(["add", "--all", "--"]).args(paths))?;
+    let unchanged = git(dir)
+        .args(["diff", "--cached", "--quiet", "--"])
+        .args(paths)
+        .status()
+        .context("Failed to run git diff")?
+        .success();
+    if unchanged {
+        return Ok(false);
+    }
+
+    run(git(dir).args(AUTHOR).args(["commit", "--quiet", "--message", message, "--"]).args(paths))?;
+    Ok(true)
+}
+
+fn git(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.current_dir(dir);
+    command
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.output().context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!("git failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(dir: &Path) -> Vec<String> {
+        let output = git(dir).args(["log", "--format=%an: %s"]).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn commits_only_the_given_paths_and_only_when_they_changed() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("examples")).unwrap();
+        fs::write(dir.join("examples/intro.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("exercise.rs"), "fn main() { todo!() }\n").unwrap();
+
+        init(dir).unwrap();
+        assert!(commit(dir, &[Path::new(".")], "Generate module").unwrap());
+        assert!(!commit(dir, &[Path::new(".")], "Nothing to commit").unwrap());
+
+        // The learner's edit of the exercise is left out of the app's commit
+        fs::write(dir.join("exercise.rs"), "fn main() {}\n").unwrap();
+        fs::write(dir.join("examples/intro.rs"), "fn main() { println!(\"hi\"); }\n").unwrap();
+        assert!(commit(dir, &[Path::new("examples/intro.rs")], "Fix snippets").unwrap());
+        assert_eq!(log(dir), ["RustMentor: Fix snippets", "RustMentor: Generate module"]);
+
+        let status = git(dir).args(["status", "--porcelain"]).output().unwrap();
+        assert_eq!(String::from_utf8_lossy(&status.stdout), " M exercise.rs\n");
+    }
+}
@@ -0,0 +1,203 @@
+// src/grader.rs
+use crate::compile_check;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Result of one hidden test of an exercise
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestOutcome {
+    // Without the module path, e.g. `counts_ascii` rather than `grading::counts_ascii`
+    pub name: String,
+    pub passed: bool,
+    // Panic message and captured output of a failed test
+    pub failure: String,
+}
+
+/// Line in the starter code of exercises without tests, which only pass once the learner removes it;
+/// starter code that already runs would pass untouched otherwise
+pub const NOT_DONE_MARKER: &str = "// I AM NOT DONE";
+
+/// Outcome of grading one exercise
+#[derive(Debug, Clone, Default)]
+pub struct GradeReport {
+    pub tests: Vec<TestOutcome>,
+    // Rendered compiler errors when the exercise and its tests do not build, or why the run failed
+    pub errors: Vec<String>,
+}
+
+impl GradeReport {
+    pub fn passed(&self) -> bool {
+        self.errors.is_empty() && !self.tests.is_empty() && self.tests.iter().all(|test| test.passed)
+    }
+}
+
+/// Grades the exercise binary `bin` with source file `path` in `project_dir`: with its hidden tests from
+/// `grading/` when it has them, otherwise by running it, which passes once it builds and exits successfully
+/// (e.g. no `todo!()` left) and the learner has removed [`NOT_DONE_MARKER`]
+pub async fn grade_exercise(project_dir: &Path, bin: &str, path: &Path, timeout: Duration) -> Result<GradeReport> {
+    if project_dir.join("grading").join(format!("{}.rs", bin)).exists() {
+        return check_exercise(project_dir, bin, timeout).await;
+    }
+
+    let mut report = run_exercise(project_dir, bin, timeout).await?;
+    let source = fs::read_to_string(project_dir.join(path)).with_context(|| format!("Failed to read {}", path.display()))?;
+    if source.lines().any(|line| line.trim() == NOT_DONE_MARKER) {
+        report.errors.push(format!("Remove the `{}` line from {} once you are done", NOT_DONE_MARKER, path.display()));
+    }
+    Ok(report)
+}
+
+// Runs the exercise binary `bin` of `project_dir`, which passes when it exits successfully
+async fn run_exercise(project_dir: &Path, bin: &str, timeout: Duration) -> Result<GradeReport> {
+    let command = Command::new("cargo")
+        .current_dir(project_dir)
+        .args(["run", "--offline", "--message-format=json", "--bin", bin])
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(timeout, command)
+        .await
+        .map_err(|_| anyhow::anyhow!("The exercise did not finish within {}s", timeout.as_secs()))?
+        .context("Failed to run cargo run")?;
+
+    let errors: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(compile_check::compiler_message)
+        .filter(|message| message.level == "error")
+        .map(|message| message.diagnostic.rendered)
+        .collect();
+    if !errors.is_empty() {
+        return Ok(GradeReport { tests: Vec::new(), errors });
+    }
+
+    // cargo's "Compiling"/"Running" lines come first on stderr, the program's panic message last
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let failure = stderr
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("Running"))
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(GradeReport {
+        tests: vec![TestOutcome {
+            name: format!("cargo run --bin {}", bin),
+            passed: output.status.success(),
+            failure,
+        }],
+        errors: Vec::new(),
+    })
+}
+
+// Runs the hidden tests of the exercise binary `bin` in `project_dir`
+async fn check_exercise(project_dir: &Path, bin: &str, timeout: Duration) -> Result<GradeReport> {
+    let command = Command::new("cargo")
+        .current_dir(project_dir)
+        .args(["test", "--offline", "--message-format=json", "--bin", bin])
+        .env("RUST_BACKTRACE", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(timeout, command)
+        .await
+        .map_err(|_| anyhow::anyhow!("The tests did not finish within {}s", timeout.as_secs()))?
+        .context("Failed to run cargo test")?;
+
+    // cargo's JSON messages and the test harness output share stdout
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let errors: Vec<String> = stdout
+        .lines()
+        .filter_map(compile_check::compiler_message)
+        .filter(|message| message.level == "error")
+        .map(|message| message.diagnostic.rendered)
+        .collect();
+    let tests = parse_test_output(&stdout);
+
+    let mut report = GradeReport { tests, errors };
+    if !output.status.success() && report.tests.is_empty() && report.errors.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        report.errors.push(stderr.lines().filter(|line| line.contains("error")).collect::<Vec<_>>().join("\n"));
+    }
+    Ok(report)
+}
+
+// Reads `test name ... ok|FAILED` lines and the `---- name stdout ----` sections libtest prints for failures
+fn parse_test_output(output: &str) -> Vec<TestOutcome> {
+    let mut tests: Vec<TestOutcome> = Vec::new();
+    let mut failure_of: Option<usize> = None;
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("test ")
+            && let Some((name, result)) = rest.split_once(" ... ")
+        {
+            failure_of = None;
+            if result == "ignored" {
+                continue;
+            }
+            tests.push(TestOutcome {
+                name: name.rsplit("::").next().unwrap_or(name).to_string(),
+                passed: result == "ok",
+                failure: String::new(),
+            });
+        } else if let Some(name) = line.strip_prefix("---- ").and_then(|rest| rest.strip_suffix(" stdout ----")) {
+            let name = name.rsplit("::").next().unwrap_or(name);
+            failure_of = tests.iter().position(|test| test.name == name);
+        } else if line == "failures:" || line.starts_with("test result:") {
+            failure_of = None;
+        } else if let Some(index) = failure_of
+            && !line.trim().is_empty()
+        {
+            let failure = &mut tests[index].failure;
+            if !failure.is_empty() {
+                failure.push('\n');
+            }
+            failure.push_str(line);
+        }
+    }
+    tests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_results_and_failure_messages() {
+        let output = "\n\
+running 3 tests
+test grading::counts_ascii ... ok
+test grading::counts_unicode ... FAILED
+test grading::slow ... ignored
+
+failures:
+
+---- grading::counts_unicode stdout ----
+
+thread 'grading::counts_unicode' panicked at src/bin/count_characters.rs:20:5:
+assertion `left == right` failed
+  left: 6
+ right: 2
+
+failures:
+    grading::counts_unicode
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+";
+        let tests = parse_test_output(output);
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].name, "counts_ascii");
+        assert!(tests[0].passed);
+        assert!(!tests[1].passed);
+        assert!(tests[1].failure.starts_with("thread 'grading::counts_unicode' panicked"));
+        assert!(tests[1].failure.ends_with(" right: 2"));
+    }
+}
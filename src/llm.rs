@@ -1,109 +1,383 @@
 // src/llm.rs
-use crate::app::LearningModule;
+use crate::app::{LearningModule, ModuleUpdate};
 use crate::data::Topic;
 use anyhow::Result;
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use crate::config::Config;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use crate::config::{Config, ModuleFormat};
+use crate::llm_provider::{self, ApiError, Completion, CompletionRequest, LlmProvider, LlmTask, Model};
+use crate::retry::RetryStatus;
+use crate::compile_check::CompilerDiagnostic;
+use crate::syntax_check::{self, SyntaxError};
+use crate::usage::{UsageLedger, UsageSnapshot};
 use crate::prompt_response::{CodeSnippet, Exercise, PromptResponse};
 
-// OpenRouter API request structure
-#[derive(Debug, Serialize)]
-struct OpenRouterRequest {
-    model: String,
-    messages: Vec<Message>,
-}
+// Output section of the module prompt for the `<<<...>>>` delimited format
+const DELIMITED_OUTPUT_RULES: &str = r#"**Output Formatting Rules:**
+- Your output should be *only* the text of the prompt described above.
+- Do not include any conversational text or explanations outside of the prompt itself.
+- Structure the entire output clearly using the following delimiters.
+- Ensure the "explanation" and "problem" sections are valid Markdown.
+- Ensure code snippets, "starter_code", "tests" and "solution" sections are valid RUST language code.
+- Output Structure Structure: 
 
-#[derive(Debug, Serialize)]
-struct Message {
-    role: String,
-    content: String,
-}
+    ```
+    <<<explanation: [explanation title]>>>
+    [Detailed explanation for the topic ...]
 
-// OpenRouter API response structure
-#[derive(Debug, Deserialize)]
-struct OpenRouterResponse {
-    choices: Vec<Choice>,
-}
+    <<<code_snippet 1: [code snippets title 1]>>>
+    // code snippet: [ code snippet description 1]
+    [ The actual example code snippet 1 ... ]
+
+    <<<code_snippet 2: [code snippet title 2]>>>
+    // code snippet: [ code snippet description 2]
+    [ The actual example code snippet 2 ... ]
+
+    <<<code_snippet n: [code snippet title n]>>>
+    // code snippet: [ code snippet description n]
+    [ The actual example code snippet n ... ]
+
+    <<<exercise 1: [ exercise name 1 ]>>>
+    // exercise description: [ one-line summary of exercise 1 ]
+    <<<problem>>>
+    [ Markdown problem statement for exercise 1 ... ]
+    <<<hints>>>
+    - [ hint 1 ]
+    - [ hint 2 ]
+    <<<expected_output>>>
+    [ What a correct solution prints ... ]
+    <<<starter_code>>>
+    [ The Rust code the learner starts from ... ]
+    <<<tests>>>
+    [ #[test] functions checking a correct solution, preceded by `use super::*;` ... ]
+    <<<solution>>>
+    [ A complete reference solution that passes the tests ... ]
 
-#[derive(Debug, Deserialize)]
-struct Choice {
-    message: ResponseMessage,
+    <<<exercise n: [ exercise name n ]>>>
+    // exercise description: [ one-line summary of exercise n ]
+    <<<problem>>>
+    [ Markdown problem statement for exercise n ... ]
+    <<<hints>>>
+    - [ hint 1 ]
+    <<<expected_output>>>
+    [ What a correct solution prints ... ]
+    <<<starter_code>>>
+    [ The Rust code the learner starts from ... ]
+    <<<tests>>>
+    [ #[test] functions checking a correct solution, preceded by `use super::*;` ... ]
+    <<<solution>>>
+    [ A complete reference solution that passes the tests ... ]
+
+    ```
+
+"#;
+
+// Output section of the module prompt for the JSON format; the schema itself is sent as `response_format`
+const JSON_OUTPUT_RULES: &str = r#"**Output Formatting Rules:**
+- Respond with a single JSON object and nothing else: no conversational text, no Markdown code fences.
+- The object has exactly these fields:
+    - "explanation": string, valid Markdown, starting with a title line.
+    - "code_snippets": array of objects with "title", "description" and "code" (valid Rust) strings.
+    - "exercises": array of objects with "name", "description" (one line), "problem_statement" (valid Markdown),
+      "hints" (array of strings), "expected_output" (string or null), "starter_code" (valid Rust), "tests"
+      (`#[test]` functions checking a correct solution, preceded by `use super::*;`) and "solution" (a complete
+      reference solution that passes the tests).
+
+"#;
+
+// A section missing from a module response, described to the model when asking for a repair
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ModuleProblem {
+    MissingExplanation,
+    NoCodeSnippets,
+    NoExercises,
 }
 
-#[derive(Debug, Deserialize)]
-struct ResponseMessage {
-    content: String,
+impl ModuleProblem {
+    fn find(response: &str) -> Vec<ModuleProblem> {
+        let Ok(parsed) = PromptResponse::parse(response) else {
+            return vec![Self::MissingExplanation, Self::NoCodeSnippets, Self::NoExercises];
+        };
+        let mut problems = Vec::new();
+        if parsed.explanation.trim().is_empty() {
+            problems.push(Self::MissingExplanation);
+        }
+        if parsed.code_snippets.is_empty() {
+            problems.push(Self::NoCodeSnippets);
+        }
+        if parsed.exercises.is_empty() {
+            problems.push(Self::NoExercises);
+        }
+        problems
+    }
+
+    fn describe(self, format: ModuleFormat) -> &'static str {
+        match (self, format) {
+            (Self::MissingExplanation, ModuleFormat::Delimited) => {
+                "There is no explanation: the response must start with a `<<<explanation: [title]>>>` header followed by the explanation."
+            }
+            (Self::NoCodeSnippets, ModuleFormat::Delimited) => {
+                "There are no code snippets: each one needs a `<<<code_snippet N: [title]>>>` header, a `// code snippet: [description]` line and the code."
+            }
+            (Self::NoExercises, ModuleFormat::Delimited) => {
+                "There are no exercises: each one needs a `<<<exercise N: [name]>>>` header, a `// exercise description: [description]` line, a `<<<problem>>>` section and a `<<<starter_code>>>` section."
+            }
+            (Self::MissingExplanation, ModuleFormat::Json) => {
+                "The response is not a JSON object with a non-empty \"explanation\" string."
+            }
+            (Self::NoCodeSnippets, ModuleFormat::Json) => {
+                "The \"code_snippets\" array is missing or empty; each entry needs \"title\", \"description\" and \"code\"."
+            }
+            (Self::NoExercises, ModuleFormat::Json) => {
+                "The \"exercises\" array is missing or empty; each entry needs \"name\", \"description\", \"problem_statement\", \"hints\", \"expected_output\" and \"starter_code\"."
+            }
+        }
+    }
+
+    fn repair_prompt(problems: &[ModuleProblem], format: ModuleFormat) -> String {
+        let mut prompt = "Your previous response could not be used as a learning module:\n".to_string();
+        for problem in problems {
+            prompt.push_str("- ");
+            prompt.push_str(problem.describe(format));
+            prompt.push('\n');
+        }
+        prompt.push_str(
+            "\nReply with the complete learning module again, fixing these problems and following the output format \
+             of the original request exactly. Do not add any other text.",
+        );
+        prompt
+    }
 }
 
 // LLM client for generating learning content
 #[derive(Clone)]
 pub struct LlmClient {
-    client: Client,
-    api_key: String,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Model {
-    pub id: String,
-    pub name: String,
+    provider: Arc<dyn LlmProvider>,
+    // Shared by every clone, so settings changed in the app reach calls already spawned with a clone
+    config: Arc<RwLock<Config>>,
+    retry_status: RetryStatus,
+    usage: UsageLedger,
 }
 
-
 impl LlmClient {
-    pub fn new(api_key: String) -> Self {
+    pub fn new(api_key: String, config: Config) -> Self {
+        let retry_status = RetryStatus::default();
+        let provider = llm_provider::from_settings(&config.provider, api_key, retry_status.clone());
+        Self {
+            retry_status,
+            usage: UsageLedger::open(config.spend_budget_usd),
+            ..Self::with_provider(provider, config)
+        }
+    }
+
+    pub fn with_provider(provider: Arc<dyn LlmProvider>, config: Config) -> Self {
         Self {
-            client: Client::new(),
-            api_key,
+            provider,
+            config: Arc::new(RwLock::new(config)),
+            retry_status: RetryStatus::default(),
+            usage: UsageLedger::in_memory(None),
         }
     }
 
-    pub async fn list_models(&self) -> Result<Vec<Model>, Box<dyn std::error::Error>> {
-        let url = "https://openrouter.ai/api/v1/models";
+    // Use settings changed after the client was created for all later calls
+    pub fn set_config(&self, config: Config) {
+        self.usage.set_budget(config.spend_budget_usd);
+        *self.config.write().unwrap() = config;
+    }
+
+    fn config(&self) -> Config {
+        self.config.read().unwrap().clone()
+    }
 
-        let resp = self.client
-            .get(url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
+    // Description of the retry in progress, if a call is currently backing off
+    pub fn retry_status(&self) -> Option<String> {
+        self.retry_status.get()
+    }
 
-        let json: serde_json::Value = resp.json().await?;
-        let models: Vec<Model> = serde_json::from_value(json["data"].clone())?;
+    // Forget a retry message left behind by a call that was aborted mid-backoff
+    pub fn clear_retry_status(&self) {
+        self.retry_status.clear();
+    }
 
-        Ok(models)
+    // Session and lifetime token usage and spend
+    pub fn usage(&self) -> UsageSnapshot {
+        self.usage.snapshot()
     }
 
-    // Generate a learning module based on a topic and user level
-    pub async fn generate_learning_module(
+    pub async fn list_models(&self) -> Result<Vec<Model>> {
+        self.provider.list_models().await
+    }
+
+    // Generate a learning module, sending partially parsed modules through `updates`
+    // while the response streams in. Each model of the fallback chain is tried in turn
+    // until one produces a usable module.
+    pub async fn stream_learning_module(
         &self,
         topic: &Topic,
         level: u8,
+        updates: &mpsc::Sender<ModuleUpdate>,
     ) -> Result<LearningModule> {
-        // Check if API key is available
-        if self.api_key.is_empty() {
-            anyhow::bail!(
-                "OpenRouter API key is not set. Please set the OPENROUTER_API_KEY environment variable."
-            );
+        let config = self.config();
+
+        let mut last_error = None;
+        let mut unusable = None;
+        for model in config.model_chain(LlmTask::Module) {
+            let mut format = config.module_format;
+            let mut result = self.module_response(&model, format, topic, level, updates).await;
+            if format == ModuleFormat::Json
+                && let Err(err) = &result
+                && Self::rejects_structured_output(err)
+            {
+                tracing::warn!("{} rejected the JSON output request ({}), retrying with delimited output", model, err);
+                format = ModuleFormat::Delimited;
+                result = self.module_response(&model, format, topic, level, updates).await;
+            }
+
+            let result = match result {
+                Ok(response) => Ok(self
+                    .repair_module(&model, format, topic, level, response, config.module_repair_attempts)
+                    .await),
+                Err(err) => Err(err),
+            };
+
+            match result {
+                Ok(response) if Self::is_usable(&response) => {
+                    return self.parse_response(response, topic, &model);
+                }
+                Ok(response) => {
+                    tracing::warn!("{} returned a module that could not be parsed, trying the next model", model);
+                    unusable.get_or_insert((response, model));
+                }
+                Err(err) => {
+                    tracing::warn!("{} failed to generate a module: {}", model, err);
+                    last_error = Some(err);
+                }
+            }
         }
 
-        // Create the prompt for the LLM
-        let prompt = self.create_prompt(topic, level);
+        // Show the best unparseable response rather than an error if any model answered at all
+        match (unusable, last_error) {
+            (Some((response, model)), _) => self.parse_response(response, topic, &model),
+            (None, Some(err)) => Err(err),
+            (None, None) => anyhow::bail!("No model configured"),
+        }
+    }
 
-        // Call the OpenRouter API
-        let response = self.call_openrouter_api(prompt).await?;
+    // Request a module from one model, streaming partial modules to the UI when enabled
+    async fn module_response(
+        &self,
+        model: &str,
+        format: ModuleFormat,
+        topic: &Topic,
+        level: u8,
+        updates: &mpsc::Sender<ModuleUpdate>,
+    ) -> Result<String> {
+        let prompt = self.create_prompt(topic, level, format);
+        let request = self.build_request(LlmTask::Module, model, prompt);
+        match format {
+            // Half a JSON object cannot be rendered, so JSON modules are never streamed
+            ModuleFormat::Json => {
+                return self.call_model(request.with_response_schema(PromptResponse::json_schema())).await;
+            }
+            ModuleFormat::Delimited if !self.config.read().unwrap().provider.stream => return self.call_model(request).await,
+            ModuleFormat::Delimited => {}
+        }
+
+        let (delta_sender, mut delta_receiver) = mpsc::unbounded_channel();
+        let request = self.call_model_streaming(request, delta_sender);
+        let forward = async {
+            let mut text = String::new();
+            while let Some(delta) = delta_receiver.recv().await {
+                text.push_str(&delta);
+                // Deltas are often a few characters; parsing the whole response again only pays off
+                // once a line is complete, and the finished response is parsed in full anyway
+                if !delta.contains('\n') {
+                    continue;
+                }
+                let partial = PromptResponse::parse_partial(&text);
+                // A full channel only means the UI has not caught up yet; the next partial supersedes this one
+                let _ = updates.try_send(ModuleUpdate::Partial(self.partial_module(partial, topic, model)));
+            }
+        };
+        let (response, ()) = tokio::join!(request, forward);
+        response
+    }
+
+    // Send a malformed module back to the model together with what is wrong with it, up to
+    // `max_attempts` times. The response with the fewest problems is kept.
+    async fn repair_module(
+        &self,
+        model: &str,
+        format: ModuleFormat,
+        topic: &Topic,
+        level: u8,
+        mut response: String,
+        max_attempts: u32,
+    ) -> String {
+        let mut problems = ModuleProblem::find(&response);
+        for attempt in 1..=max_attempts {
+            if problems.is_empty() {
+                break;
+            }
+            tracing::warn!(
+                "{} returned a malformed module ({:?}), repair attempt {}/{}",
+                model,
+                problems,
+                attempt,
+                max_attempts
+            );
 
-        // Save the response to a file for debugging
-        //let current_datetime = Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
-        //let filename = format!("response_{}.txt", current_datetime);
-        //std::fs::write(&filename, &response)?;
-        // Parse the response into a LearningModule
-        self.parse_response(response, topic)
+            let prompt = self.create_prompt(topic, level, format);
+            let mut request = self
+                .build_request(LlmTask::Module, model, prompt)
+                .with_follow_up(response.clone(), ModuleProblem::repair_prompt(&problems, format));
+            if format == ModuleFormat::Json {
+                request = request.with_response_schema(PromptResponse::json_schema());
+            }
+
+            match self.call_model(request).await {
+                Ok(repaired) => {
+                    let remaining = ModuleProblem::find(&repaired);
+                    tracing::info!("Repair attempt {}/{} left {:?}", attempt, max_attempts, remaining);
+                    if remaining.len() <= problems.len() {
+                        response = repaired;
+                        problems = remaining;
+                    }
+                }
+                Err(err) => {
+                    tracing::warn!("Repair attempt {}/{} failed: {}", attempt, max_attempts, err);
+                    break;
+                }
+            }
+        }
+        response
+    }
+
+    // A client error on a JSON request usually means the model or server does not support `response_format`
+    fn rejects_structured_output(error: &anyhow::Error) -> bool {
+        error
+            .downcast_ref::<ApiError>()
+            .is_some_and(|api_error| matches!(api_error.status.as_u16(), 400 | 422))
+    }
+
+    // Build a module from a response that is still streaming, without any placeholder content
+    fn partial_module(&self, partial: PromptResponse, topic: &Topic, model: &str) -> LearningModule {
+        LearningModule {
+            topic: topic.topic.clone(),
+            explanation: partial.explanation,
+            code_snippets: partial.code_snippets,
+            exercises: partial.exercises,
+            additional_resources: None,
+            model: Some(model.to_string()),
+            // Half-received sections would only produce noise, so diagnostics wait for the full response
+            diagnostics: Vec::new(),
+        }
     }
 
     // Create a prompt for the LLM based on the topic, level, and customization options
     // In your struct impl
-    fn create_prompt(&self, topic: &Topic, level: u8) -> String {
-        let config = Config::load().unwrap();
+    fn create_prompt(&self, topic: &Topic, level: u8, format: ModuleFormat) -> String {
+        let config = self.config();
         let customization = config.content_customization;
 
         let level_description = match level {
@@ -142,54 +416,22 @@ impl LlmClient {
 
         let learning_goal = customization.learning_goal.to_string();
 
+        let output_rules = match format {
+            ModuleFormat::Delimited => DELIMITED_OUTPUT_RULES,
+            ModuleFormat::Json => JSON_OUTPUT_RULES,
+        };
+
         // The refined prompt is much more explicit and strict.
         format!(
             r#"
 You are an expert Rust programming language tutor and a specialist in generating structured data.
 Your task is to create a learning module about the topic '{topic}' for a Rust programmer at the '{level_description}' level but focus on  '{learning_goal}' leaning goal subjet.
 
-**Output Formatting Rules:**
-- Your output should be *only* the text of the prompt described above.
-- Do not include any conversational text or explanations outside of the prompt itself.
-- Structure the entire output clearly using the following delimiters.
-- Ensure the "explanation",  "exercise descriptions" sections are valid Markdown.
-- Ensure "exercise code"  sections are valid RUST language code.
-- Output Structure Structure: 
-
-    ```
-    <<<explanation: [explanation title]>>>
-    [Detailed explanation for the topic ...]
-
-    <<<code_snippet 1: [code snippets title 1]>>>
-    // code snippet: [ code snippet description 1]
-    [ The actual example code snippet 1 ... ]
-
-    <<<code_snippet 2: [code snippet title 2]>>>
-    // code snippet: [ code snippet description 2]
-    [ The actual example code snippet 2 ... ]
-
-    <<<code_snippet n: [code snippet title n]>>>
-    // code snippet: [ code snippet description n]
-    [ The actual example code snippet n ... ]
-
-    <<<exercise 1: [ exercise name 1 ]>>>
-    // exercise description: [  exercise description 1 ]
-    [ The actual exercise 1 code ... ]
-
-    <<<exercise 2: [ exercise name 2 ]>>>
-    // exercise description: [ exercise description 2] 
-    [ The actual exercise 2 code ... ]
-
-    <<<exercise n: [ exercise name n ]>>>
-    // exercise description: [ exercise description n] 
-    [ The actual exercise n code ... ]
-
-    ```
-
-**Content Guidelines:**
+{output_rules}**Content Guidelines:**
 -   `explanation`: Provide a {verbosity_text} explanation of the topic, tailored to the '{level_description}' level while focusing on subject "'{learning_goal}'" as the learning goal.
 -   `code_snippets`: Provide several complete, runnable, and well-commented Rust code examples. The code should be {complexity_text}, appropriate for the target level.
 -   `exercises`: Provide several distinct practice exercises. They should be clear problem statements that allow the user to apply the concepts from the explanation and code snippets.
+    The tests are compiled as a `#[cfg(test)]` module inside the learner's file, so they can only call items of the starter code; keep function names and signatures of the starter code in the solution.
 -   {focus_instruction}
 
 **Request:**
@@ -201,61 +443,201 @@ Source of this topic: {source}
             source = topic.source,
             verbosity_text = verbosity_text,
             complexity_text = complexity_text,
-            focus_instruction = focus_instruction
+            focus_instruction = focus_instruction,
+            output_rules = output_rules
         )
     }
 
-    // Call the OpenRouter API with the prompt
-    pub async fn call_openrouter_api(&self, prompt: String) -> Result<String> {
-        // Create the request body
-        let model_id = Config::load().unwrap().model;
-        let request = OpenRouterRequest {
-            model: model_id, // You can change this to a different model if needed
-            messages: vec![Message {
-                role: "user".to_string(),
-                content: prompt,
-            }],
-        };
+    // Ask for a corrected version of a code snippet that does not parse, returning only the new code
+    pub async fn regenerate_snippet(&self, topic: &str, snippet: &CodeSnippet, error: &SyntaxError) -> Result<String> {
+        let prompt = format!(
+            r#"
+The following Rust code snippet from a learning module about '{topic}' does not compile.
+
+Title: {title}
+Description: {description}
+Parse error at {error}
+
+```rust
+{code}
+```
+
+Reply with only the corrected, complete Rust code for this snippet, keeping its purpose and comments.
+Do not include any explanation or Markdown code fences.
+"#,
+            topic = topic,
+            title = snippet.title,
+            description = snippet.description,
+            error = error,
+            code = snippet.code
+        );
+
+        self.corrected_code(prompt).await
+    }
+
+    // Ask for starter code of an exercise that parses, returning only the new code
+    pub async fn regenerate_exercise(&self, topic: &str, exercise: &Exercise, error: &SyntaxError) -> Result<String> {
+        let prompt = format!(
+            r#"
+The starter code of the following exercise from a learning module about '{topic}' is not valid Rust.
+
+Exercise: {name}
+Description: {description}
+{problem_statement}
+Parse error at {error}
+
+```rust
+{code}
+```
+
+Reply with only the corrected starter code, keeping its comments and leaving the exercise for the learner to solve.
+Keep the names and signatures of its functions, since the exercise's tests call them.
+Do not include any explanation or Markdown code fences.
+"#,
+            topic = topic,
+            name = exercise.name,
+            description = exercise.description,
+            problem_statement = exercise.problem_statement,
+            error = error,
+            code = exercise.starter_code
+        );
+
+        self.corrected_code(prompt).await
+    }
+
+    // Ask for a version of a code snippet that builds, given the errors `cargo check` reported for it
+    pub async fn fix_compile_errors(&self, topic: &str, snippet: &CodeSnippet, errors: &[CompilerDiagnostic]) -> Result<String> {
+        let rendered = errors
+            .iter()
+            .map(|error| error.rendered.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let prompt = format!(
+            r#"
+The following Rust code snippet from a learning module about '{topic}' is built as a Cargo example
+(a file in `examples/`, preceded by two comment lines) and fails to compile.
+
+Title: {title}
+Description: {description}
 
-        // Make the API call
-        let response = self
-            .client
-            .post("https://openrouter.ai/api/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        // Check if the request was successful
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            anyhow::bail!("OpenRouter API request failed: {}", error_text);
+```rust
+{code}
+```
+
+The compiler reported:
+
+{rendered}
+
+Reply with only the corrected, complete Rust code for this snippet, keeping its purpose and comments.
+It must compile on its own with the standard library, so include `fn main` and every `use` it needs.
+Do not include any explanation or Markdown code fences.
+"#,
+            topic = topic,
+            title = snippet.title,
+            description = snippet.description,
+            code = snippet.code,
+            rendered = rendered
+        );
+
+        self.corrected_code(prompt).await
+    }
+
+    async fn corrected_code(&self, prompt: String) -> Result<String> {
+        let response = self.call_llm_api(LlmTask::Snippet, prompt).await?;
+        // Models add fences despite being asked not to
+        let code = response
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("```"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(code.trim_matches('\n').to_string() + "\n")
+    }
+
+    // Send the prompt to the configured provider, falling back along the model chain on errors
+    pub async fn call_llm_api(&self, task: LlmTask, prompt: String) -> Result<String> {
+        let mut last_error = None;
+        for model in self.config().model_chain(task) {
+            match self.call_model(self.build_request(task, &model, prompt.clone())).await {
+                Ok(response) => return Ok(response),
+                Err(err) => {
+                    tracing::warn!("{} failed for {} prompt: {}", model, task, err);
+                    last_error = Some(err);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| anyhow::anyhow!("No model configured")))
+    }
+
+    // Apply the task's sampling parameters and system prompt to the request
+    fn build_request(&self, task: LlmTask, model: &str, prompt: String) -> CompletionRequest {
+        let config = self.config();
+        let profile = config.tasks.profile(task);
+        let request = CompletionRequest::new(task, model.to_string(), prompt).with_sampling(profile.sampling());
+        match &profile.system_prompt {
+            Some(system_prompt) => request.with_system_prompt(system_prompt.clone()),
+            None => request,
         }
+    }
+
+    // Send the request to its model
+    async fn call_model(&self, request: CompletionRequest) -> Result<String> {
+        self.usage.check_budget()?;
 
-        // Parse the response
-        let response_data: OpenRouterResponse = response.json().await?;
+        tracing::debug!("Sending {} prompt to {} ({})", request.task, self.provider.name(), request.model);
+        let completion = self.provider.complete(&request).await?;
+        Ok(self.account(request.task, &request.model, completion))
+    }
+
+    // Like `call_model`, but forwards text deltas through `deltas` as they arrive
+    async fn call_model_streaming(&self, request: CompletionRequest, deltas: mpsc::UnboundedSender<String>) -> Result<String> {
+        self.usage.check_budget()?;
 
-        // Extract the content from the response
-        if let Some(choice) = response_data.choices.first() {
-            Ok(choice.message.content.clone())
-        } else {
-            anyhow::bail!("No content in OpenRouter API response");
+        tracing::debug!("Streaming {} prompt to {} ({})", request.task, self.provider.name(), request.model);
+        let completion = self.provider.stream(&request, deltas).await?;
+        Ok(self.account(request.task, &request.model, completion))
+    }
+
+    // Record the completion's usage in the ledger and return its text
+    fn account(&self, task: LlmTask, model: &str, completion: Completion) -> String {
+        match completion.usage {
+            Some(usage) => {
+                tracing::debug!(
+                    "{} call used {} prompt + {} completion tokens",
+                    task,
+                    usage.prompt_tokens,
+                    usage.completion_tokens
+                );
+                self.usage.record(task, model, usage);
+            }
+            None => tracing::debug!("{} call reported no usage", task),
         }
+        completion.content
+    }
+
+    // Whether a response contains anything the module parser recognizes
+    fn is_usable(response: &str) -> bool {
+        PromptResponse::parse(response).is_ok_and(|parsed| parsed.has_content())
     }
 
     // Parse the LLM response into a LearningModule
     // Assuming RawLearningModule is defined as above
     // And LearningModule is the struct you want to create
 
-    pub(crate) fn parse_response(&self, response: String, topic: &Topic) -> Result<LearningModule> {
+    pub(crate) fn parse_response(&self, response: String, topic: &Topic, model: &str) -> Result<LearningModule> {
 
-        let prompt_res = PromptResponse::parse_response(response.clone());
+        let prompt_res = PromptResponse::parse(&response).and_then(|parsed| {
+            if parsed.has_content() {
+                Ok(parsed)
+            } else {
+                // Every recognized section was empty; the diagnostics say which ones were dropped
+                Err(parsed.diagnostics)
+            }
+        });
         match prompt_res {
             Ok(prompt_res) => {
                 // Now you have a strongly-typed struct.
                 // You can add extra checks here if you want (e.g., ensure vecs are not empty).
-                Ok(LearningModule {
+                let mut module = LearningModule {
                     topic: topic.topic.clone(),
                     explanation: prompt_res.explanation,
                     code_snippets: if prompt_res.code_snippets.is_empty() {
@@ -263,6 +645,7 @@ Source of this topic: {source}
                             title: "No code examples provided".to_string(),
                             description: "// No code examples provided".to_string(),
                             code: "// No code examples provided".to_string(),
+                            syntax_error: None,
                         }]
                     } else {
                         prompt_res.code_snippets
@@ -271,18 +654,30 @@ Source of this topic: {source}
                         vec![Exercise {
                             name: "No exercises provided".to_string(),
                             description: "// No exercises provided".to_string(),
-                            code: "// No exercises provided".to_string(),
+                            problem_statement: String::new(),
+                            hints: Vec::new(),
+                            expected_output: None,
+                            starter_code: "// No exercises provided".to_string(),
+                            tests: String::new(),
+                            solution: String::new(),
+                            syntax_error: None,
                             }
                         ]
                     } else {
                         prompt_res.exercises
                     },
                     additional_resources: None, // Will be populated by the App when displayed
-                })
+                    model: Some(model.to_string()),
+                    diagnostics: prompt_res.diagnostics,
+                };
+                syntax_check::validate_module(&mut module);
+                Ok(module)
             }
-            Err(e) => {
+            Err(diagnostics) => {
                 // This fallback will now be triggered far less often.
-                tracing::warn!("Failed to parse LLM response as JSON: {}", e);
+                for diagnostic in &diagnostics {
+                    tracing::warn!("Failed to parse LLM response: {}", diagnostic);
+                }
                 tracing::debug!("Problematic response body: {}", response); // Log the body for debugging
 
                 // Your existing fallback logic is fine.
@@ -296,15 +691,122 @@ Source of this topic: {source}
                         title: "Error - No code examples extracted.".to_string(),
                         description: "// No code examples extracted".to_string(),
                         code: "// No code examples extracted".to_string(),
+                        syntax_error: None,
                     }],
                     exercises: vec![Exercise {
                         name: "Error - No exercises extracted from LLM response".to_string(),
                         description: "// No exercises ...".to_string(),
-                        code: "// No code provided".to_string(),
+                        problem_statement: String::new(),
+                        hints: Vec::new(),
+                        expected_output: None,
+                        starter_code: "// No code provided".to_string(),
+                        tests: String::new(),
+                        solution: String::new(),
+                        syntax_error: None,
                     }],
                     additional_resources: None,
+                    model: Some(model.to_string()),
+                    diagnostics,
                 })
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::BoxFuture;
+    use std::sync::Mutex;
+
+    // Serves canned responses in order and keeps every request it receives
+    struct ScriptedProvider {
+        responses: Mutex<Vec<String>>,
+        requests: Mutex<Vec<CompletionRequest>>,
+    }
+
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &'static str {
+            "Scripted"
+        }
+
+        fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+            Box::pin(async move {
+                self.requests.lock().unwrap().push(request.clone());
+                Ok(Completion::text(self.responses.lock().unwrap().remove(0)))
+            })
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+    }
+
+    // The defaults, independent of the developer's ~/rust-mentor.conf
+    fn unstreamed_config() -> Config {
+        let mut config = Config::default();
+        config.provider.stream = false;
+        config
+    }
+
+    #[tokio::test]
+    async fn malformed_module_is_sent_back_for_repair() {
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec![
+                "<<<explanation: Traits>>>\nShared behaviour.\n".to_string(),
+                "<<<explanation: Traits>>>\nShared behaviour.\n<<<code_snippet 1: Impl>>>\nimpl Foo for Bar {}\n\
+                 <<<exercise 1: Implement>>>\nfn main() {}\n"
+                    .to_string(),
+            ]),
+            requests: Mutex::new(Vec::new()),
+        });
+        let client = LlmClient::with_provider(provider.clone(), unstreamed_config());
+        let topic = Topic {
+            topic: "Traits".to_string(),
+            source: "test".to_string(),
+            min_level: 1,
+        };
+
+        let (updates, _receiver) = mpsc::channel(10);
+        let module = client.stream_learning_module(&topic, 5, &updates).await.unwrap();
+        assert_eq!(module.code_snippets[0].title, "Impl");
+        assert_eq!(module.exercises[0].name, "Implement");
+
+        let requests = provider.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        let repair = &requests[1].messages;
+        assert_eq!(repair[repair.len() - 2].role, "assistant");
+        let follow_up = &repair[repair.len() - 1].content;
+        assert!(follow_up.contains("no code snippets"));
+        assert!(follow_up.contains("no exercises"));
+        assert!(!follow_up.contains("no explanation"));
+    }
+
+    #[tokio::test]
+    async fn compile_errors_are_sent_with_the_snippet() {
+        let provider = Arc::new(ScriptedProvider {
+            responses: Mutex::new(vec!["```rust\nfn main() {\n    let x: u32 = 1;\n}\n```".to_string()]),
+            requests: Mutex::new(Vec::new()),
+        });
+        let client = LlmClient::with_provider(provider.clone(), unstreamed_config());
+        let snippet = CodeSnippet {
+            title: "Types".to_string(),
+            description: "Annotated bindings".to_string(),
+            code: "fn main() {\n    let x: u32 = \"1\";\n}\n".to_string(),
+            syntax_error: None,
+        };
+        let errors = vec![CompilerDiagnostic {
+            message: "mismatched types".to_string(),
+            line: Some(5),
+            rendered: "error[E0308]: mismatched types\n --> examples/types.rs:5:18\n".to_string(),
+        }];
+
+        let code = client.fix_compile_errors("Types", &snippet, &errors).await.unwrap();
+        assert_eq!(code, "fn main() {\n    let x: u32 = 1;\n}\n");
+
+        let requests = provider.requests.lock().unwrap();
+        let prompt = &requests[0].messages.last().unwrap().content;
+        assert!(prompt.contains("error[E0308]: mismatched types"));
+        assert!(prompt.contains("let x: u32 = \"1\";"));
+    }
+}
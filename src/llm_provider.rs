@@ -0,0 +1,915 @@
+// src/llm_provider.rs
+use crate::cassette;
+use crate::config::{ProviderKind, ProviderSettings};
+use crate::retry::{RetryStatus, RetryingProvider};
+use anyhow::Result;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Boxed future returned by provider methods so the trait stays object safe
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A single chat message sent to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+/// The feature a prompt was sent for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmTask {
+    Module,
+    Questions,
+    Application,
+    // Rewrites of a single code snippet or exercise of a module
+    Snippet,
+}
+
+impl LlmTask {
+    /// Stable lowercase identifier, used e.g. for fixture file names
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LlmTask::Module => "module",
+            LlmTask::Questions => "questions",
+            LlmTask::Application => "application",
+            LlmTask::Snippet => "snippet",
+        }
+    }
+}
+
+impl fmt::Display for LlmTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Sampling parameters; unset values leave the provider's defaults in place
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SamplingOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
+/// Provider-agnostic completion request
+#[derive(Debug, Clone)]
+pub struct CompletionRequest {
+    pub task: LlmTask,
+    pub model: String,
+    pub messages: Vec<Message>,
+    pub sampling: SamplingOptions,
+    /// JSON schema the response must conform to, for providers with structured output support
+    pub response_schema: Option<JsonSchema>,
+}
+
+/// Named JSON schema for structured output
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonSchema {
+    pub name: &'static str,
+    pub schema: serde_json::Value,
+}
+
+impl CompletionRequest {
+    /// Creates a request containing a single user prompt
+    pub fn new(task: LlmTask, model: String, prompt: String) -> Self {
+        Self {
+            task,
+            model,
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: prompt,
+            }],
+            sampling: SamplingOptions::default(),
+            response_schema: None,
+        }
+    }
+
+    /// Prepends a system message to the conversation
+    pub fn with_system_prompt(mut self, system_prompt: String) -> Self {
+        self.messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: system_prompt,
+            },
+        );
+        self
+    }
+
+    pub fn with_sampling(mut self, sampling: SamplingOptions) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Continues the conversation with the model's previous answer and a follow-up prompt
+    pub fn with_follow_up(mut self, answer: String, prompt: String) -> Self {
+        self.messages.push(Message {
+            role: "assistant".to_string(),
+            content: answer,
+        });
+        self.messages.push(Message {
+            role: "user".to_string(),
+            content: prompt,
+        });
+        self
+    }
+
+    pub fn with_response_schema(mut self, schema: JsonSchema) -> Self {
+        self.response_schema = Some(schema);
+        self
+    }
+}
+
+/// A model advertised by a provider
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Model {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub context_length: Option<u64>,
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// USD price per token, as reported by OpenRouter
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ModelPricing {
+    #[serde(default)]
+    pub prompt: String,
+    #[serde(default)]
+    pub completion: String,
+}
+
+impl ModelPricing {
+    /// Prompt and completion prices per million tokens, if both parse
+    pub fn per_million(&self) -> Option<(f64, f64)> {
+        let prompt: f64 = self.prompt.parse().ok()?;
+        let completion: f64 = self.completion.parse().ok()?;
+        Some((prompt * 1_000_000.0, completion * 1_000_000.0))
+    }
+}
+
+impl Model {
+    /// Free models carry a `:free` suffix on OpenRouter, or report zero pricing
+    pub fn is_free(&self) -> bool {
+        self.id.ends_with(":free")
+            || self
+                .pricing
+                .as_ref()
+                .and_then(ModelPricing::per_million)
+                .is_some_and(|(prompt, completion)| prompt == 0.0 && completion == 0.0)
+    }
+}
+
+/// Token counts reported by the provider for one call
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost in USD, when the provider reports it (OpenRouter)
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+/// The text of a completion plus the usage it was billed for
+#[derive(Debug, Clone, Default)]
+pub struct Completion {
+    pub content: String,
+    pub usage: Option<TokenUsage>,
+}
+
+impl Completion {
+    pub fn text(content: String) -> Self {
+        Self { content, usage: None }
+    }
+}
+
+/// A backend capable of turning prompts into completions
+pub trait LlmProvider: Send + Sync {
+    /// Human readable provider name used in logs and error messages
+    fn name(&self) -> &'static str;
+
+    /// Sends the request and returns the first completion
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>>;
+
+    /// Streams the completion, sending text deltas as they arrive and returning the full completion.
+    /// Providers without streaming support deliver the whole completion as a single delta.
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let completion = self.complete(request).await?;
+            let _ = deltas.send(completion.content.clone());
+            Ok(completion)
+        })
+    }
+
+    /// Lists the models available from this provider
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>>;
+}
+
+/// Environment variable that points the app at a fixture directory, overriding the configured provider
+pub const MOCK_LLM_ENV: &str = "RUST_MENTOR_MOCK_LLM";
+
+/// Builds the provider selected in the configuration with the retry policy applied,
+/// wrapped in a cassette when recording or replaying
+pub fn from_settings(settings: &ProviderSettings, api_key: String, retry_status: RetryStatus) -> Arc<dyn LlmProvider> {
+    let provider = Arc::new(RetryingProvider::new(
+        base_provider(settings, api_key),
+        settings.retry.clone(),
+        retry_status,
+    ));
+    cassette::wrap(settings, provider)
+}
+
+fn base_provider(settings: &ProviderSettings, api_key: String) -> Arc<dyn LlmProvider> {
+    if let Ok(fixtures_dir) = std::env::var(MOCK_LLM_ENV) {
+        tracing::info!("Using mock LLM provider with fixtures from {}", fixtures_dir);
+        return Arc::new(MockProvider::new(PathBuf::from(fixtures_dir)));
+    }
+
+    let base_url = settings.base_url.clone();
+    match settings.kind {
+        ProviderKind::OpenRouter => Arc::new(OpenRouterProvider::new(
+            base_url.unwrap_or_else(|| OpenRouterProvider::DEFAULT_BASE_URL.to_string()),
+            api_key,
+        )),
+        ProviderKind::OpenAiCompatible => Arc::new(OpenAiCompatibleProvider {
+            stream_usage: settings.stream_usage,
+            ..OpenAiCompatibleProvider::new(
+                base_url.unwrap_or_else(|| OpenAiCompatibleProvider::DEFAULT_BASE_URL.to_string()),
+                api_key,
+            )
+        }),
+        ProviderKind::Ollama => Arc::new(OllamaProvider::new(
+            base_url.unwrap_or_else(|| OllamaProvider::DEFAULT_BASE_URL.to_string()),
+        )),
+        ProviderKind::Mock => Arc::new(MockProvider::new(
+            settings
+                .fixtures_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(MockProvider::DEFAULT_FIXTURES_DIR)),
+        )),
+    }
+}
+
+// Joins a base URL and a path without doubling the slash
+fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), path)
+}
+
+/// A non-2xx response from a provider's HTTP API
+#[derive(Debug)]
+pub struct ApiError {
+    pub provider: String,
+    pub status: StatusCode,
+    /// Delay requested by the server through the `Retry-After` header
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} API request failed ({}): {}", self.provider, self.status, self.body)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// Turns a non-2xx response into an `ApiError` carrying the response body
+async fn ensure_success(provider: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    // Only the delay-seconds form is handled; HTTP dates fall back to regular backoff
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let body = response.text().await?;
+    Err(ApiError {
+        provider: provider.to_string(),
+        status,
+        retry_after,
+        body,
+    }
+    .into())
+}
+
+// Reads a streamed response body line by line until `on_line` returns false or the body ends
+async fn for_each_line(
+    mut response: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<bool>,
+) -> Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.extend_from_slice(&chunk);
+        // Only decode complete lines so multi-byte characters are never split
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            if !on_line(String::from_utf8_lossy(&line).trim())? {
+                return Ok(());
+            }
+        }
+    }
+    if !buffer.is_empty() {
+        on_line(String::from_utf8_lossy(&buffer).trim())?;
+    }
+    Ok(())
+}
+
+// OpenAI chat-completions request structure
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(flatten)]
+    sampling: &'a SamplingOptions,
+    // Asks for a final chunk carrying token usage when streaming
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+    // OpenRouter extension that adds the cost to the reported usage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<UsageAccounting>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat<'a>>,
+}
+
+// Structured output request: `{"type": "json_schema", "json_schema": {...}}`
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ResponseFormat<'a> {
+    JsonSchema { json_schema: StrictSchema<'a> },
+}
+
+#[derive(Debug, Serialize)]
+struct StrictSchema<'a> {
+    #[serde(flatten)]
+    schema: &'a JsonSchema,
+    strict: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageAccounting {
+    include: bool,
+}
+
+// OpenAI chat-completions response structure
+#[derive(Debug, Deserialize)]
+struct ChatCompletionResponse {
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseMessage {
+    content: String,
+}
+
+// OpenAI server-sent event chunk
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    usage: Option<TokenUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    delta: ChunkDelta,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ModelList {
+    data: Vec<Model>,
+}
+
+/// Any server implementing the OpenAI chat-completions API (vLLM, llama.cpp, LM Studio, ...)
+pub struct OpenAiCompatibleProvider {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    // Request OpenRouter's usage accounting, which plain OpenAI servers reject
+    usage_accounting: bool,
+    // Request a final chunk with token usage when streaming, which older servers reject
+    stream_usage: bool,
+}
+
+impl OpenAiCompatibleProvider {
+    pub const DEFAULT_BASE_URL: &'static str = "http://localhost:8000/v1";
+
+    pub fn new(base_url: String, api_key: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url,
+            api_key,
+            usage_accounting: false,
+            stream_usage: true,
+        }
+    }
+
+    // Local servers usually run without authentication, so the header is optional
+    fn authorize(&self, builder: RequestBuilder) -> RequestBuilder {
+        if self.api_key.is_empty() {
+            builder
+        } else {
+            builder.header("Authorization", format!("Bearer {}", self.api_key))
+        }
+    }
+
+    fn chat_request<'a>(&self, request: &'a CompletionRequest, stream: bool) -> ChatCompletionRequest<'a> {
+        ChatCompletionRequest {
+            model: &request.model,
+            messages: &request.messages,
+            stream,
+            sampling: &request.sampling,
+            stream_options: (stream && self.stream_usage).then_some(StreamOptions { include_usage: true }),
+            usage: self.usage_accounting.then_some(UsageAccounting { include: true }),
+            response_format: request.response_schema.as_ref().map(|schema| ResponseFormat::JsonSchema {
+                json_schema: StrictSchema { schema, strict: true },
+            }),
+        }
+    }
+
+    async fn send_chat_request(&self, provider: &str, request: &CompletionRequest, stream: bool) -> Result<reqwest::Response> {
+        let body = self.chat_request(request, stream);
+
+        let response = self
+            .authorize(self.client.post(endpoint(&self.base_url, "chat/completions")))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .send()
+            .await?;
+        ensure_success(provider, response).await
+    }
+
+    async fn chat_completion(&self, provider: &str, request: &CompletionRequest) -> Result<Completion> {
+        let response = self.send_chat_request(provider, request, false).await?;
+
+        let response_data: ChatCompletionResponse = response.json().await?;
+        match response_data.choices.into_iter().next() {
+            Some(choice) => Ok(Completion {
+                content: choice.message.content,
+                usage: response_data.usage,
+            }),
+            None => anyhow::bail!("No content in {} API response", provider),
+        }
+    }
+
+    async fn chat_completion_stream(
+        &self,
+        provider: &str,
+        request: &CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> Result<Completion> {
+        let response = self.send_chat_request(provider, request, true).await?;
+
+        let mut content = String::new();
+        let mut usage = None;
+        for_each_line(response, |line| {
+            // Blank lines separate events and lines starting with ':' are keep-alive comments
+            let Some(data) = line.strip_prefix("data:") else {
+                return Ok(true);
+            };
+            let data = data.trim();
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+            let chunk: ChatCompletionChunk = serde_json::from_str(data)?;
+            // Usage arrives in the last chunk, which has no choices
+            if chunk.usage.is_some() {
+                usage = chunk.usage;
+            }
+            if let Some(delta) = chunk.choices.into_iter().next().and_then(|c| c.delta.content) {
+                content.push_str(&delta);
+                let _ = deltas.send(delta);
+            }
+            Ok(true)
+        })
+        .await?;
+
+        if content.is_empty() {
+            anyhow::bail!("No content in {} API response", provider);
+        }
+        Ok(Completion { content, usage })
+    }
+
+    async fn models(&self, provider: &str) -> Result<Vec<Model>> {
+        let response = self
+            .authorize(self.client.get(endpoint(&self.base_url, "models")))
+            .send()
+            .await?;
+        let response = ensure_success(provider, response).await?;
+        let models: ModelList = response.json().await?;
+        Ok(models.data)
+    }
+}
+
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &'static str {
+        "OpenAI-compatible"
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(self.chat_completion(self.name(), request))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(self.chat_completion_stream(self.name(), request, deltas))
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(self.models(self.name()))
+    }
+}
+
+/// OpenRouter, which speaks the OpenAI API but always requires an API key
+pub struct OpenRouterProvider {
+    inner: OpenAiCompatibleProvider,
+}
+
+impl OpenRouterProvider {
+    pub const DEFAULT_BASE_URL: &'static str = "https://openrouter.ai/api/v1";
+
+    pub fn new(base_url: String, api_key: String) -> Self {
+        Self {
+            inner: OpenAiCompatibleProvider {
+                usage_accounting: true,
+                ..OpenAiCompatibleProvider::new(base_url, api_key)
+            },
+        }
+    }
+
+    fn ensure_api_key(&self) -> Result<()> {
+        if self.inner.api_key.is_empty() {
+            anyhow::bail!(
+                "OpenRouter API key is not set. Please set the OPENROUTER_API_KEY environment variable."
+            );
+        }
+        Ok(())
+    }
+}
+
+impl LlmProvider for OpenRouterProvider {
+    fn name(&self) -> &'static str {
+        "OpenRouter"
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            self.ensure_api_key()?;
+            self.inner.chat_completion(self.name(), request).await
+        })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            self.ensure_api_key()?;
+            self.inner.chat_completion_stream(self.name(), request, deltas).await
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(self.inner.models(self.name()))
+    }
+}
+
+// Ollama native chat request structure
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    stream: bool,
+    options: OllamaOptions,
+    // Ollama accepts the bare JSON schema as the output format
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<&'a serde_json::Value>,
+}
+
+// Ollama takes sampling parameters under `options`, with its own name for the token limit
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    num_predict: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatResponse {
+    message: ResponseMessage,
+    #[serde(default)]
+    done: bool,
+    // Token counts, only present on the final response
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+impl OllamaChatResponse {
+    fn usage(&self) -> Option<TokenUsage> {
+        if !self.done {
+            return None;
+        }
+        Some(TokenUsage {
+            prompt_tokens: self.prompt_eval_count.unwrap_or(0),
+            completion_tokens: self.eval_count.unwrap_or(0),
+            cost: None,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaTags {
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModel {
+    name: String,
+}
+
+/// A local Ollama server using its native `/api/chat` endpoint
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+}
+
+impl OllamaProvider {
+    pub const DEFAULT_BASE_URL: &'static str = "http://localhost:11434";
+
+    pub fn new(base_url: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url,
+        }
+    }
+
+    async fn send_chat_request(&self, request: &CompletionRequest, stream: bool) -> Result<reqwest::Response> {
+        let body = OllamaChatRequest {
+            model: &request.model,
+            messages: &request.messages,
+            stream,
+            options: OllamaOptions {
+                temperature: request.sampling.temperature,
+                top_p: request.sampling.top_p,
+                num_predict: request.sampling.max_tokens,
+            },
+            format: request.response_schema.as_ref().map(|schema| &schema.schema),
+        };
+
+        let response = self
+            .client
+            .post(endpoint(&self.base_url, "api/chat"))
+            .json(&body)
+            .send()
+            .await?;
+        ensure_success(self.name(), response).await
+    }
+}
+
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &'static str {
+        "Ollama"
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let response = self.send_chat_request(request, false).await?;
+            let response_data: OllamaChatResponse = response.json().await?;
+            Ok(Completion {
+                usage: response_data.usage(),
+                content: response_data.message.content,
+            })
+        })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            let response = self.send_chat_request(request, true).await?;
+
+            // Ollama streams one JSON object per line
+            let mut content = String::new();
+            let mut usage = None;
+            for_each_line(response, |line| {
+                if line.is_empty() {
+                    return Ok(true);
+                }
+                let chunk: OllamaChatResponse = serde_json::from_str(line)?;
+                usage = usage.or(chunk.usage());
+                if !chunk.message.content.is_empty() {
+                    content.push_str(&chunk.message.content);
+                    let _ = deltas.send(chunk.message.content);
+                }
+                Ok(!chunk.done)
+            })
+            .await?;
+            Ok(Completion { content, usage })
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(async move {
+            let response = self
+                .client
+                .get(endpoint(&self.base_url, "api/tags"))
+                .send()
+                .await?;
+            let response = ensure_success(self.name(), response).await?;
+
+            let tags: OllamaTags = response.json().await?;
+            Ok(tags
+                .models
+                .into_iter()
+                .map(|model| Model {
+                    id: model.name.clone(),
+                    name: model.name,
+                    ..Model::default()
+                })
+                .collect())
+        })
+    }
+}
+
+/// Offline provider that answers every request with the fixture file for its task
+/// (`module.txt`, `questions.txt` or `application.txt`), for tests and demos
+pub struct MockProvider {
+    fixtures_dir: PathBuf,
+}
+
+impl MockProvider {
+    pub const DEFAULT_FIXTURES_DIR: &'static str = "tests/fixtures/llm";
+
+    pub fn new(fixtures_dir: PathBuf) -> Self {
+        Self { fixtures_dir }
+    }
+
+    fn fixture(&self, task: LlmTask) -> Result<String> {
+        let path = self.fixtures_dir.join(format!("{}.txt", task));
+        std::fs::read_to_string(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to read mock fixture {}: {}", path.display(), err))
+    }
+}
+
+impl LlmProvider for MockProvider {
+    fn name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move { self.fixture(request.task).map(Completion::text) })
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(async move {
+            // Deliver the fixture line by line to exercise the streaming path
+            let content = self.fixture(request.task)?;
+            for line in content.split_inclusive('\n') {
+                let _ = deltas.send(line.to_string());
+            }
+            Ok(Completion::text(content))
+        })
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(async move {
+            Ok(vec![Model {
+                id: "mock".to_string(),
+                name: "Mock fixtures".to_string(),
+                ..Model::default()
+            }])
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_request_only_serializes_configured_sampling() {
+        let request = CompletionRequest::new(LlmTask::Questions, "m".to_string(), "prompt".to_string())
+            .with_system_prompt("Be brief".to_string())
+            .with_sampling(SamplingOptions {
+                temperature: Some(0.2),
+                top_p: None,
+                max_tokens: Some(512),
+            });
+        let body = ChatCompletionRequest {
+            model: &request.model,
+            messages: &request.messages,
+            stream: false,
+            sampling: &request.sampling,
+            stream_options: None,
+            usage: None,
+            response_format: None,
+        };
+
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["messages"][0]["role"], "system");
+        assert_eq!(json["messages"][1]["content"], "prompt");
+        assert_eq!(json["max_tokens"], 512);
+        assert!((json["temperature"].as_f64().unwrap() - 0.2).abs() < 1e-6);
+        assert!(json.get("top_p").is_none());
+        assert!(json.get("stream").is_none());
+        assert!(json.get("response_format").is_none());
+    }
+
+    #[test]
+    fn chat_request_serializes_response_schema() {
+        let schema = JsonSchema {
+            name: "answer",
+            schema: serde_json::json!({"type": "object"}),
+        };
+        let body = ChatCompletionRequest {
+            model: "m",
+            messages: &[],
+            stream: false,
+            sampling: &SamplingOptions::default(),
+            stream_options: None,
+            usage: None,
+            response_format: Some(ResponseFormat::JsonSchema {
+                json_schema: StrictSchema { schema: &schema, strict: true },
+            }),
+        };
+
+        let json = serde_json::to_value(&body).unwrap();
+        assert_eq!(json["response_format"]["type"], "json_schema");
+        assert_eq!(json["response_format"]["json_schema"]["name"], "answer");
+        assert_eq!(json["response_format"]["json_schema"]["strict"], true);
+        assert_eq!(json["response_format"]["json_schema"]["schema"]["type"], "object");
+    }
+
+    #[test]
+    fn stream_usage_can_be_turned_off_for_older_servers() {
+        let request = CompletionRequest::new(LlmTask::Module, "m".to_string(), "prompt".to_string());
+        let provider = OpenAiCompatibleProvider::new("http://localhost:8000/v1".to_string(), String::new());
+        let json = serde_json::to_value(provider.chat_request(&request, true)).unwrap();
+        assert_eq!(json["stream_options"]["include_usage"], true);
+
+        let provider = OpenAiCompatibleProvider {
+            stream_usage: false,
+            ..provider
+        };
+        let json = serde_json::to_value(provider.chat_request(&request, true)).unwrap();
+        assert!(json.get("stream_options").is_none());
+    }
+}
@@ -1,38 +1,78 @@
 // src/main.rs
 mod app;
+mod cassette;
 mod components;
 mod data;
 mod event;
 mod llm;
+mod llm_provider;
+mod model_picker;
 mod tui;
 mod ui;
 mod prompt_response;
 mod config;
 mod cargo_project;
 mod question_generator;
+mod retry;
+mod usage;
+mod syntax_check;
+mod compile_check;
+mod snippet_runner;
+mod grader;
+mod watch;
+mod dependencies;
+mod git;
+#[cfg(test)]
+mod test_support;
 
 use anyhow::Result;
 use app::App;
 use event::{Event, EventHandler};
 use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
 use tui::Tui;
-use crate::config::ConfigService;
+use crate::config::{ConfigService, ProviderKind};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging
     tracing_subscriber::fmt::init();
 
-    ConfigService::new();
+    let config_service = ConfigService::new();
 
-    // Read OPENROUTER_API_KEY environment variable
-    let api_key = env::var("OPENROUTER_API_KEY").unwrap_or_else(|_| {
-        tracing::warn!("OPENROUTER_API_KEY environment variable not set");
-        String::new()
-    });
+    // `RustMentor watch [DIR]` grades the exercises of a generated project without the TUI
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("watch") {
+        let project_dir = args.get(1).map(PathBuf::from).unwrap_or_else(|| PathBuf::from("."));
+        let timeout = Duration::from_secs(config_service.get_config().snippet_run_timeout_secs);
+        return watch::run_cli(&project_dir, timeout).await;
+    }
+
+    let provider = &config_service.get_config().provider;
+
+    // Without a cost per call the budget could never be reached, so it would silently allow unlimited spend
+    if config_service.get_config().spend_budget_usd.is_some() && !provider.reports_cost() {
+        anyhow::bail!(
+            "spend_budget_usd is set in ~/rust-mentor.conf, but the {:?} provider does not report the cost of calls. \
+             Remove spend_budget_usd or use OpenRouter.",
+            provider.kind
+        );
+    }
+
+    // Read the API key for the configured provider (OPENROUTER_API_KEY by default)
+    let api_key = match provider.api_key_var() {
+        Some(var) => env::var(var).unwrap_or_else(|_| {
+            if provider.kind == ProviderKind::OpenRouter {
+                tracing::warn!("{} environment variable not set", var);
+            }
+            String::new()
+        }),
+        None => String::new(),
+    };
 
     // Create the application state
-    let mut app = App::new(api_key);
+    let mut app = App::new(api_key, config_service);
 
     // Initialize the terminal user interface
     let mut tui = Tui::new()?;
@@ -0,0 +1,68 @@
+// src/model_picker.rs
+use crate::llm_provider::Model;
+
+/// State of the searchable model list in the Settings screen
+#[derive(Default)]
+pub struct ModelPicker {
+    pub models: Vec<Model>,
+    pub loading: bool,
+    pub error: Option<String>,
+    pub query: String,
+    pub searching: bool, // Typed keys go into `query` instead of being handled as commands
+    pub free_only: bool,
+    pub cursor: usize,
+}
+
+impl ModelPicker {
+    /// Models matching the search query (by id or name) and the free filter
+    pub fn filtered(&self) -> Vec<&Model> {
+        let query = self.query.to_lowercase();
+        self.models
+            .iter()
+            .filter(|model| !self.free_only || model.is_free())
+            .filter(|model| {
+                query.is_empty()
+                    || model.id.to_lowercase().contains(&query)
+                    || model.name.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<&Model> {
+        self.filtered().get(self.cursor).copied()
+    }
+
+    pub fn move_up(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        let count = self.filtered().len();
+        if self.cursor + 1 < count {
+            self.cursor += 1;
+        }
+    }
+
+    pub fn toggle_free_only(&mut self) {
+        self.free_only = !self.free_only;
+        self.cursor = 0;
+    }
+
+    pub fn push_query(&mut self, c: char) {
+        self.query.push(c);
+        self.cursor = 0;
+    }
+
+    pub fn pop_query(&mut self) {
+        self.query.pop();
+        self.cursor = 0;
+    }
+
+    /// Replace the model list, keeping the cursor on `current` when it is listed
+    pub fn set_models(&mut self, models: Vec<Model>, current: &str) {
+        self.models = models;
+        self.loading = false;
+        self.error = None;
+        self.cursor = self.filtered().iter().position(|model| model.id == current).unwrap_or(0);
+    }
+}
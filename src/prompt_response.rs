@@ -1,11 +1,21 @@
+use crate::llm_provider::JsonSchema;
+use crate::syntax_check::SyntaxError;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashSet;
+use std::fmt;
 
 // Define the structs to hold the parsed data
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct PromptResponse {
     pub explanation: String,
+    #[serde(default)]
     pub code_snippets: Vec<CodeSnippet>,
+    #[serde(default)]
     pub exercises: Vec<Exercise>,
+    // Warnings from the delimiter parser; JSON responses have none
+    #[serde(skip)]
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,113 +23,490 @@ pub struct CodeSnippet {
     pub title: String,
     pub description: String,
     pub code: String,
+    // Set by `syntax_check::validate_module` when the code does not parse
+    #[serde(skip)]
+    pub syntax_error: Option<SyntaxError>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Exercise {
     pub name: String,
+    // One-line summary shown under the name
     pub description: String,
-    pub code: String,
+    // Markdown task description
+    #[serde(default)]
+    pub problem_statement: String,
+    #[serde(default)]
+    pub hints: Vec<String>,
+    // What a correct solution prints
+    #[serde(default)]
+    pub expected_output: Option<String>,
+    // Code the learner starts from
+    pub starter_code: String,
+    // `#[test]` functions run against the learner's code by (t) Check solution; not shown in the TUI
+    #[serde(default)]
+    pub tests: String,
+    // Reference solution the tests pass against, written next to the project for after the attempt
+    #[serde(default)]
+    pub solution: String,
+    #[serde(skip)]
+    pub syntax_error: Option<SyntaxError>,
 }
 
 impl PromptResponse {
-    pub fn parse_response(response: String) -> Result<Self, String> {
-        let mut explanation = String::new();
-        let mut code_snippets = Vec::new();
-        let mut exercises = Vec::new();
-
-        let mut current_section = String::new();
-        let mut current_code_snippet = CodeSnippet {
-            title: String::new(),
-            description: String::new(),
-            code: String::new(),
+    /// Schema of the JSON output mode, mirroring the fields of `PromptResponse`
+    pub fn json_schema() -> JsonSchema {
+        let code_snippet = json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "description": { "type": "string" },
+                "code": { "type": "string" },
+            },
+            "required": ["title", "description", "code"],
+            "additionalProperties": false,
+        });
+        let exercise = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "description": { "type": "string" },
+                "problem_statement": { "type": "string" },
+                "hints": { "type": "array", "items": { "type": "string" } },
+                "expected_output": { "type": ["string", "null"] },
+                "starter_code": { "type": "string" },
+                "tests": { "type": "string" },
+                "solution": { "type": "string" },
+            },
+            "required": [
+                "name",
+                "description",
+                "problem_statement",
+                "hints",
+                "expected_output",
+                "starter_code",
+                "tests",
+                "solution"
+            ],
+            "additionalProperties": false,
+        });
+        JsonSchema {
+            name: "learning_module",
+            schema: json!({
+                "type": "object",
+                "properties": {
+                    "explanation": { "type": "string" },
+                    "code_snippets": { "type": "array", "items": code_snippet },
+                    "exercises": { "type": "array", "items": exercise },
+                },
+                "required": ["explanation", "code_snippets", "exercises"],
+                "additionalProperties": false,
+            }),
+        }
+    }
+
+    /// Parses either output format: JSON is tried first, then the `<<<...>>>` delimiters
+    pub fn parse(response: &str) -> Result<Self, Vec<Diagnostic>> {
+        match Self::parse_json(response) {
+            Ok(parsed) => Ok(parsed),
+            Err(json_error) => {
+                tracing::debug!("Response is not a JSON module ({}), using the delimiter parser", json_error);
+                Self::parse_response(response)
+            }
+        }
+    }
+
+    /// Deserializes a JSON module, tolerating a surrounding Markdown code fence
+    pub fn parse_json(response: &str) -> Result<Self, String> {
+        let trimmed = response.trim();
+        let body = trimmed
+            .strip_prefix("```json")
+            .or_else(|| trimmed.strip_prefix("```"))
+            .and_then(|rest| rest.trim_end().strip_suffix("```"))
+            .unwrap_or(trimmed);
+        serde_json::from_str(body).map_err(|err| err.to_string())
+    }
+
+    /// True when at least one section was recognized in the response
+    pub fn has_content(&self) -> bool {
+        !self.explanation.trim().is_empty() || !self.code_snippets.is_empty() || !self.exercises.is_empty()
+    }
+
+    /// Parses a response that is still being streamed. A trailing line that could be the
+    /// start of a `<<<...>>>` delimiter is held back until the rest of it arrives.
+    pub fn parse_partial(response: &str) -> Self {
+        let last_line_start = response.rfind('\n').map_or(0, |i| i + 1);
+        let complete = if response[last_line_start..].starts_with('<') {
+            &response[..last_line_start]
+        } else {
+            response
         };
-        let mut current_exercise = Exercise {
-            name: String::new(),
-            description: String::new(),
-            code: String::new(),
+
+        match Self::parse_response(complete) {
+            Ok(parsed) => parsed,
+            Err(_) => PromptResponse {
+                explanation: String::new(),
+                code_snippets: Vec::new(),
+                exercises: Vec::new(),
+                diagnostics: Vec::new(),
+            },
+        }
+    }
+
+    /// Parses the `<<<...>>>` delimited format. Recoverable problems are collected in
+    /// `diagnostics`; a response without any recognized section is an error.
+    pub fn parse_response(response: &str) -> Result<Self, Vec<Diagnostic>> {
+        let mut parser = DelimitedParser::default();
+        for (index, line) in response.lines().enumerate() {
+            parser.line(index + 1, line);
+        }
+        parser.finish()
+    }
+}
+
+/// Problem found while parsing a delimited response, with its 1-based line number
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    // A `<<<...>>>` header that is not an explanation, code snippet or exercise
+    UnknownDelimiter(String),
+    // A snippet or exercise without a title or code, which is dropped
+    EmptySection(String),
+    UnterminatedCodeFence,
+    // Two snippets or exercises with the same number
+    DuplicateNumber(String),
+    NoSections,
+}
+
+impl Diagnostic {
+    /// Errors mean nothing usable was parsed; everything else is a warning about lost content
+    pub fn is_error(&self) -> bool {
+        self.kind == DiagnosticKind::NoSections
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            DiagnosticKind::UnknownDelimiter(header) => {
+                write!(f, "unknown delimiter <<<{}>>>, its text was added to the previous section", header)
+            }
+            DiagnosticKind::EmptySection(section) => write!(f, "{} is empty and was skipped", section),
+            DiagnosticKind::UnterminatedCodeFence => write!(f, "code fence is never closed"),
+            DiagnosticKind::DuplicateNumber(section) => write!(f, "{} is numbered twice", section),
+            DiagnosticKind::NoSections => {
+                write!(f, "no <<<explanation>>>, <<<code_snippet>>> or <<<exercise>>> sections found")
+            }
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum Section {
+    #[default]
+    None,
+    Explanation,
+    CodeSnippet,
+    Exercise(ExercisePart),
+}
+
+// Sub-section of an exercise, selected by `<<<problem>>>`, `<<<hints>>>`, ... headers.
+// Text before the first of them is starter code.
+#[derive(Clone, Copy, PartialEq)]
+enum ExercisePart {
+    ProblemStatement,
+    Hints,
+    ExpectedOutput,
+    StarterCode,
+    Tests,
+    Solution,
+}
+
+// Comment lines carrying descriptions; models use `//` as the prompt asks, older responses used `#`
+const CODE_SNIPPET_DESCRIPTION: [&str; 2] = ["// code snippet:", "# code snippet:"];
+const EXERCISE_DESCRIPTION: [&str; 2] = ["// exercise description:", "# exercise description:"];
+
+fn strip_any_prefix<'a>(line: &'a str, prefixes: &[&str]) -> Option<&'a str> {
+    prefixes.iter().find_map(|prefix| line.strip_prefix(prefix))
+}
+
+// Line-by-line state of `PromptResponse::parse_response`
+#[derive(Default)]
+struct DelimitedParser {
+    explanation: String,
+    code_snippets: Vec<CodeSnippet>,
+    exercises: Vec<Exercise>,
+    diagnostics: Vec<Diagnostic>,
+    section: Section,
+    found_section: bool,
+    // Header line of the snippet or exercise being read
+    current_code_snippet: Option<(usize, CodeSnippet)>,
+    current_exercise: Option<(usize, Exercise)>,
+    snippet_numbers: HashSet<u32>,
+    exercise_numbers: HashSet<u32>,
+    open_fence: Option<usize>,
+}
+
+impl DelimitedParser {
+    fn line(&mut self, line_number: usize, raw_line: &str) {
+        if raw_line.trim_start().starts_with("```") {
+            self.open_fence = match self.open_fence {
+                Some(_) => None,
+                None => Some(line_number),
+            };
+        }
+        let line = raw_line.replace("```rust", "").replace("```", "");
+
+        if let Some(header) = line.strip_prefix("<<<") {
+            self.header(line_number, &line, header.trim_end().trim_end_matches('>'));
+        } else if self.section == Section::CodeSnippet
+            && let Some(description) = strip_any_prefix(line.trim_start(), &CODE_SNIPPET_DESCRIPTION)
+            && let Some((_, snippet)) = &mut self.current_code_snippet
+        {
+            snippet.description = description.trim().to_string();
+        } else if matches!(self.section, Section::Exercise(_))
+            && let Some(description) = strip_any_prefix(line.trim_start(), &EXERCISE_DESCRIPTION)
+            && let Some((_, exercise)) = &mut self.current_exercise
+        {
+            exercise.description = description.trim().to_string();
+        } else {
+            self.push_text(&line);
+        }
+    }
+
+    fn header(&mut self, line_number: usize, line: &str, header: &str) {
+        // "code_snippet 2: Title" -> ("code_snippet", Some(2), "Title")
+        let (kind, title) = header.split_once(':').unwrap_or((header, ""));
+        let title = title.trim().to_string();
+        let (name, number) = match kind.trim().split_once(' ') {
+            Some((name, number)) => (name, number.trim().parse::<u32>().ok()),
+            None => (kind.trim(), None),
         };
 
-        for line in response.lines() {
-            let line = line
-                .replace("```rust","")
-                .replace("```","");
-                
-            if line.starts_with("<<<explanation:") {
-                current_section = "explanation".to_string();
-                // Extract the explanation title
-                let title_part = line.trim_start_matches("<<<explanation:").trim_matches('>').trim();
-                explanation.push_str(title_part);
-                explanation.push('\n');
-            } else if line.starts_with("<<<code_snippet") {
-                if !current_code_snippet.title.is_empty() && !current_code_snippet.code.is_empty() {
-                    code_snippets.push(current_code_snippet.clone());
-                    current_code_snippet = CodeSnippet {
-                        title: String::new(),
-                        description: String::new(),
-                        code: String::new(),
-                    };
-                }
-                current_section = "code_snippet".to_string();
+        if name.starts_with("explanation") {
+            self.found_section = true;
+            self.section = Section::Explanation;
+            self.explanation.push_str(&title);
+            self.explanation.push('\n');
+        } else if name.starts_with("code_snippet") {
+            self.found_section = true;
+            self.finish_code_snippet();
+            self.section = Section::CodeSnippet;
+            if let Some(number) = number
+                && !self.snippet_numbers.insert(number)
+            {
+                self.warn(line_number, DiagnosticKind::DuplicateNumber(format!("code snippet {}", number)));
+            }
+            self.current_code_snippet = Some((
+                line_number,
+                CodeSnippet {
+                    title,
+                    description: String::new(),
+                    code: String::new(),
+                    syntax_error: None,
+                },
+            ));
+        } else if name.starts_with("exercise") {
+            self.found_section = true;
+            self.finish_exercise();
+            self.section = Section::Exercise(ExercisePart::StarterCode);
+            if let Some(number) = number
+                && !self.exercise_numbers.insert(number)
+            {
+                self.warn(line_number, DiagnosticKind::DuplicateNumber(format!("exercise {}", number)));
+            }
+            self.current_exercise = Some((
+                line_number,
+                Exercise {
+                    name: title,
+                    description: String::new(),
+                    problem_statement: String::new(),
+                    hints: Vec::new(),
+                    expected_output: None,
+                    starter_code: String::new(),
+                    tests: String::new(),
+                    solution: String::new(),
+                    syntax_error: None,
+                },
+            ));
+        } else if let Section::Exercise(_) = self.section
+            && let Some(part) = exercise_part(name)
+        {
+            self.section = Section::Exercise(part);
+        } else {
+            self.warn(line_number, DiagnosticKind::UnknownDelimiter(header.to_string()));
+            self.push_text(line);
+        }
+    }
 
-                // Extract the code snippet title, handling the numbering
-                if let Some(title_part) = line.split(':').nth(1) {
-                    current_code_snippet.title = title_part.trim_matches('>').trim().to_string();
-                }
-            } else if line.starts_with("<<<exercise") {
-                if !current_exercise.name.is_empty() && !current_exercise.code.is_empty() {
-                    exercises.push(current_exercise.clone());
-                    current_exercise = Exercise {
-                        name: String::new(),
-                        description: String::new(),
-                        code: String::new(),
-                    };
+    // Append a body line to the section being read
+    fn push_text(&mut self, line: &str) {
+        let target = match self.section {
+            Section::Explanation => &mut self.explanation,
+            Section::CodeSnippet => match &mut self.current_code_snippet {
+                Some((_, snippet)) => &mut snippet.code,
+                None => return,
+            },
+            Section::Exercise(part) => {
+                let Some((_, exercise)) = &mut self.current_exercise else {
+                    return;
+                };
+                match part {
+                    ExercisePart::ProblemStatement => &mut exercise.problem_statement,
+                    ExercisePart::StarterCode => &mut exercise.starter_code,
+                    ExercisePart::Tests => &mut exercise.tests,
+                    ExercisePart::Solution => &mut exercise.solution,
+                    ExercisePart::ExpectedOutput => exercise.expected_output.get_or_insert_with(String::new),
+                    // One hint per line, list markers removed
+                    ExercisePart::Hints => {
+                        let hint = line.trim().trim_start_matches(['-', '*']).trim();
+                        if !hint.is_empty() {
+                            exercise.hints.push(hint.to_string());
+                        }
+                        return;
+                    }
                 }
-                current_section = "exercise".to_string();
+            }
+            Section::None => return,
+        };
+        target.push_str(line);
+        target.push('\n');
+    }
 
-                // Extract the exercise name, handling the numbering
-                if let Some(name_part) = line.split(':').nth(1) {
-                    current_exercise.name = name_part.trim_matches('>').trim().to_string();
-                }
-            } else if line.starts_with("# code snippet:") {
-                if current_section == "code_snippet" {
-                    current_code_snippet.description = line.trim_start_matches("# code snippet:").trim().to_string();
-                }
-            } else if line.starts_with("# exercise description:") {
-                if current_section == "exercise" {
-                    current_exercise.description = line.trim_start_matches("# exercise description:").trim().to_string();
-                }
+    fn finish_code_snippet(&mut self) {
+        if let Some((line, snippet)) = self.current_code_snippet.take() {
+            if snippet.title.is_empty() || snippet.code.trim().is_empty() {
+                self.warn(line, DiagnosticKind::EmptySection(section_label("code snippet", &snippet.title)));
             } else {
-                match current_section.as_str() {
-                    "explanation" => {
-                        explanation.push_str(line.as_str());
-                        explanation.push('\n');
-                    },
-                    "code_snippet" => {
-                        current_code_snippet.code.push_str(line.as_str());
-                        current_code_snippet.code.push('\n');
-                    },
-                    "exercise" => {
-                        current_exercise.code.push_str(line.as_str());
-                        current_exercise.code.push('\n');
-                    },
-                    _ => (),
-                }
+                self.code_snippets.push(snippet);
+            }
+        }
+    }
+
+    fn finish_exercise(&mut self) {
+        if let Some((line, mut exercise)) = self.current_exercise.take() {
+            if exercise.name.is_empty()
+                || (exercise.starter_code.trim().is_empty() && exercise.problem_statement.trim().is_empty())
+            {
+                self.warn(line, DiagnosticKind::EmptySection(section_label("exercise", &exercise.name)));
+            } else {
+                exercise.problem_statement = exercise.problem_statement.trim().to_string();
+                exercise.expected_output = exercise
+                    .expected_output
+                    .map(|output| output.trim_matches('\n').to_string())
+                    .filter(|output| !output.trim().is_empty());
+                self.exercises.push(exercise);
             }
         }
+    }
 
-        // Push the last code snippet or exercise if they are not empty
-        if !current_code_snippet.title.is_empty() && !current_code_snippet.code.is_empty() {
-            code_snippets.push(current_code_snippet);
+    fn warn(&mut self, line: usize, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic { line, kind });
+    }
+
+    fn finish(mut self) -> Result<PromptResponse, Vec<Diagnostic>> {
+        self.finish_code_snippet();
+        self.finish_exercise();
+        if let Some(line) = self.open_fence {
+            self.warn(line, DiagnosticKind::UnterminatedCodeFence);
         }
-        if !current_exercise.name.is_empty() && !current_exercise.code.is_empty() {
-            exercises.push(current_exercise);
+        if !self.found_section {
+            self.warn(1, DiagnosticKind::NoSections);
+            return Err(self.diagnostics);
         }
 
         Ok(PromptResponse {
-            explanation,
-            code_snippets,
-            exercises,
+            explanation: self.explanation,
+            code_snippets: self.code_snippets,
+            exercises: self.exercises,
+            diagnostics: self.diagnostics,
         })
     }
 }
+
+fn exercise_part(name: &str) -> Option<ExercisePart> {
+    match name {
+        "problem" | "problem_statement" => Some(ExercisePart::ProblemStatement),
+        "hints" => Some(ExercisePart::Hints),
+        "expected_output" => Some(ExercisePart::ExpectedOutput),
+        "starter_code" => Some(ExercisePart::StarterCode),
+        "tests" => Some(ExercisePart::Tests),
+        "solution" => Some(ExercisePart::Solution),
+        _ => None,
+    }
+}
+
+// `code snippet "Title"`, or `untitled code snippet`
+fn section_label(section: &str, title: &str) -> String {
+    if title.is_empty() {
+        format!("untitled {}", section)
+    } else {
+        format!("{} \"{}\"", section, title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fenced_json_and_falls_back_to_delimiters() {
+        let json = r#"```json
+{
+  "explanation": "Ownership\nEach value has one owner.",
+  "code_snippets": [{"title": "Moves", "description": "A move", "code": "let a = String::new();\nlet b = a;"}],
+  "exercises": []
+}
+```"#;
+        let parsed = PromptResponse::parse(json).unwrap();
+        assert!(parsed.explanation.starts_with("Ownership"));
+        assert_eq!(parsed.code_snippets[0].title, "Moves");
+        assert!(parsed.code_snippets[0].code.contains("let b = a;"));
+        assert!(parsed.exercises.is_empty());
+
+        let delimited = "<<<explanation: Ownership>>>\nEach value has one owner.\n<<<code_snippet 1: Moves>>>\nlet b = a;\n";
+        let parsed = PromptResponse::parse(delimited).unwrap();
+        assert!(parsed.explanation.starts_with("Ownership"));
+        assert_eq!(parsed.code_snippets[0].title, "Moves");
+    }
+
+    #[test]
+    fn reports_lost_content_with_line_numbers() {
+        let response = "\
+<<<explanation: Lifetimes>>>
+References must not outlive their data.
+<<<example 1: Extra>>>
+<<<code_snippet 1: Borrow>>>
+```rust
+let r = &x;
+<<<code_snippet 1: Empty>>>
+<<<exercise 1: Annotate>>>
+fn longest<'a>() {}
+";
+        let parsed = PromptResponse::parse_response(response).unwrap();
+        assert_eq!(parsed.code_snippets.len(), 1);
+        assert_eq!(parsed.exercises.len(), 1);
+
+        let found: Vec<(usize, DiagnosticKind)> =
+            parsed.diagnostics.iter().map(|diagnostic| (diagnostic.line, diagnostic.kind.clone())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (3, DiagnosticKind::UnknownDelimiter("example 1: Extra".to_string())),
+                (7, DiagnosticKind::DuplicateNumber("code snippet 1".to_string())),
+                (7, DiagnosticKind::EmptySection("code snippet \"Empty\"".to_string())),
+                (5, DiagnosticKind::UnterminatedCodeFence),
+            ]
+        );
+        assert!(parsed.diagnostics.iter().all(|diagnostic| !diagnostic.is_error()));
+
+        let errors = PromptResponse::parse_response("just some prose").unwrap_err();
+        assert!(errors[0].is_error());
+    }
+}
@@ -1,6 +1,7 @@
 // src/question_generator.rs
 use crate::app::LearningGoal;
 use crate::llm::LlmClient;
+use crate::llm_provider::LlmTask;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -98,7 +99,29 @@ pub struct GeneratedApplication {
     pub name: String,
     pub description: String,
     pub features: Vec<String>,
+    // Titled snippets of the older response format, placed by their titles
     pub code_snippets: Vec<crate::prompt_response::CodeSnippet>,
+    // Files with explicit paths; when present they make up the project instead of `code_snippets`
+    #[serde(default)]
+    pub files: Vec<GeneratedFile>,
+}
+
+/// A file of a generated application, e.g. `src/net/mod.rs`, or a `Cargo.toml` fragment to merge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GeneratedFile {
+    // Relative to the project directory, as written by the model; validated before it is written
+    pub path: String,
+    pub content: String,
+}
+
+impl GeneratedApplication {
+    /// The file to show first: `src/main.rs` if there is one, otherwise the first Rust file
+    pub fn main_file(&self) -> Option<&GeneratedFile> {
+        self.files
+            .iter()
+            .find(|file| file.path == "src/main.rs")
+            .or_else(|| self.files.iter().find(|file| file.path.ends_with(".rs")))
+    }
 }
 
 /// The question generator module
@@ -118,7 +141,7 @@ impl QuestionGenerator {
         let prompt = self.create_questions_prompt(topic, learning_goal, question_type, num_questions);
         
         // Call the LLM API
-        let response = self.llm_client.call_openrouter_api(prompt).await?;
+        let response = self.llm_client.call_llm_api(LlmTask::Questions, prompt).await?;
         
         // Parse the response into a QuestionSet
         let questions = self.parse_questions_response(response, topic)?;
@@ -210,6 +233,7 @@ Make sure all questions help steer the LLM toward designing a complete, compelli
         let mut current_options = Vec::new();
         let mut in_question = false;
         let mut in_options = false;
+        let option_re = Regex::new(r#"(?m)^\s*\(([a-zA-Z0-9])\)\s+(.*)"#)?;
 
         for line in response.lines() {
             let line = line.trim();
@@ -269,10 +293,8 @@ Make sure all questions help steer the LLM toward designing a complete, compelli
                     // Parse option lines - look for patterns like "(1) Text" or "(y) Text"
 
                     if  line.starts_with('(') {
-                        let re = Regex::new(r#"(?m)^\s*\(([a-zA-Z0-9])\)\s+(.*)"#)?;
-
-                        // Use captures_iter to find all matches and their capture groups.
-                        let cap = re.captures(line).unwrap();
+                        // Use captures to find the option id and text.
+                        let cap = option_re.captures(line).unwrap();
 
                         let id = &cap[1].trim();
                         let text = &cap[2].trim();
@@ -319,7 +341,7 @@ Make sure all questions help steer the LLM toward designing a complete, compelli
         let prompt = self.create_application_prompt(question_set);
         
         // Call the LLM API
-        let response = self.llm_client.call_openrouter_api(prompt).await?;
+        let response = self.llm_client.call_llm_api(LlmTask::Application, prompt).await?;
         
         // Parse the response into a GeneratedApplication
         self.parse_application_response(response, &question_set.topic)
@@ -379,15 +401,23 @@ Format your response as follows:
 - ...
 <<<end>>>
 
-<<<code_snippet:Main Code>>>
-[MAIN CODE OF THE APPLICATION]
+<<<file:src/main.rs>>>
+[MAIN CODE OF THE APPLICATION, DECLARING ITS MODULES WITH `mod`]
+<<<end>>>
+
+<<<file:src/[MODULE]/mod.rs>>>
+[CODE OF THE MODULE]
 <<<end>>>
 
-<<<code_snippet:Additional Module 1>>>
-[CODE FOR ADDITIONAL MODULE]
+<<<file:Cargo.toml>>>
+[dependencies]
+[ONLY THE [dependencies] AND [dev-dependencies] ENTRIES THE APPLICATION NEEDS, IF ANY]
 <<<end>>>
 
-You can include more code snippets as needed.
+Add one file section per file, with its path relative to the project root: modules under src/ (e.g. src/net/mod.rs
+or src/storage.rs). Keep everything in the binary crate, put tests in `#[cfg(test)] mod tests` inside the module they
+test rather than under tests/, declare every module file with `mod` so the project compiles, and leave out the
+Cargo.toml section when the standard library is enough.
 "#);
 
         prompt
@@ -400,18 +430,26 @@ You can include more code snippets as needed.
         let mut features = Vec::new();
         let mut code_snippets = Vec::new();
         
+        let mut files = Vec::new();
+        
         let mut current_section = String::new();
         let mut current_content = String::new();
         let mut current_title = String::new();
         
-        for line in response.lines() {
-            let line = line
+        for raw_line in response.lines() {
+            let line = raw_line
                 .replace("```rust","")
                 .replace("```","");
 
             let line = line.trim();
             
-            if line.starts_with("<<<application_name>>>") {
+            if current_section == "file" && !line.starts_with("<<<end>>>") {
+                // Files keep their indentation; only code fence lines are dropped
+                if !raw_line.trim_start().starts_with("```") {
+                    current_content.push_str(raw_line);
+                    current_content.push('\n');
+                }
+            } else if line.starts_with("<<<application_name>>>") {
                 current_section = "name".to_string();
                 current_content = String::new();
             } else if line.starts_with("<<<application_description>>>") {
@@ -426,6 +464,7 @@ You can include more code snippets as needed.
                         title: current_title.clone(),
                         description: String::new(), // We don't have descriptions in this format
                         code: current_content.clone(),
+                        syntax_error: None,
                     });
                 }
                 
@@ -436,6 +475,10 @@ You can include more code snippets as needed.
                 if let Some(title_part) = line.split(':').nth(1) {
                     current_title = title_part.trim_end_matches(">>>").trim().to_string();
                 }
+            } else if let Some(path) = line.strip_prefix("<<<file:") {
+                current_section = "file".to_string();
+                current_content = String::new();
+                current_title = path.trim_end_matches(">>>").trim().to_string();
             } else if line.starts_with("<<<end>>>") {
                 match current_section.as_str() {
                     "name" => name = current_content.trim().to_string(),
@@ -454,6 +497,16 @@ You can include more code snippets as needed.
                                 title: current_title.clone(),
                                 description: String::new(), // We don't have descriptions in this format
                                 code: current_content.clone(),
+                                syntax_error: None,
+                            });
+                        }
+                        current_title = String::new();
+                    }
+                    "file" => {
+                        if !current_title.is_empty() {
+                            files.push(GeneratedFile {
+                                path: current_title.clone(),
+                                content: current_content.clone(),
                             });
                         }
                         current_title = String::new();
@@ -479,6 +532,7 @@ You can include more code snippets as needed.
             description,
             features,
             code_snippets,
+            files,
         })
     }
 }
\ No newline at end of file
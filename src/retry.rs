@@ -0,0 +1,286 @@
+// src/retry.rs
+use crate::config::RetrySettings;
+use crate::llm_provider::{ApiError, BoxFuture, Completion, CompletionRequest, LlmProvider, Model};
+use anyhow::{Context, Result};
+use rand::Rng;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Human readable description of the retry currently in progress, shared with the UI
+#[derive(Clone, Default)]
+pub struct RetryStatus(Arc<Mutex<Option<String>>>);
+
+impl RetryStatus {
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set(&self, message: String) {
+        *self.0.lock().unwrap() = Some(message);
+    }
+
+    pub fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+}
+
+/// Applies the configured timeout to every attempt and retries rate limits (429),
+/// server errors (5xx), timeouts and connection failures with exponential backoff
+pub struct RetryingProvider {
+    inner: Arc<dyn LlmProvider>,
+    settings: RetrySettings,
+    status: RetryStatus,
+}
+
+impl RetryingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, settings: RetrySettings, status: RetryStatus) -> Self {
+        Self { inner, settings, status }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.settings.timeout_secs)
+    }
+
+    // Runs `attempt` until it succeeds, fails permanently, or the attempt budget is spent
+    async fn with_retries<F, Fut>(&self, request: &CompletionRequest, mut attempt: F) -> Result<Completion>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Completion>>,
+    {
+        let max_attempts = self.settings.max_attempts.max(1);
+        let mut attempt_number = 1;
+        let result = loop {
+            let error = match attempt().await {
+                Ok(completion) => break Ok(completion),
+                Err(error) => error,
+            };
+            if attempt_number >= max_attempts || !is_retryable(&error) {
+                break Err(error);
+            }
+
+            let delay = self.backoff(attempt_number, &error);
+            tracing::warn!(
+                "{} {} request failed (attempt {}/{}), retrying in {:.1}s: {}",
+                self.inner.name(),
+                request.task,
+                attempt_number,
+                max_attempts,
+                delay.as_secs_f32(),
+                error
+            );
+            self.status.set(format!(
+                "{}, retrying in {}s (attempt {}/{})",
+                short_reason(&error),
+                delay.as_secs_f32().ceil(),
+                attempt_number + 1,
+                max_attempts
+            ));
+            tokio::time::sleep(delay).await;
+            attempt_number += 1;
+        };
+        self.status.clear();
+        result
+    }
+
+    // Exponential backoff with jitter, unless the server told us how long to wait
+    fn backoff(&self, attempt_number: u32, error: &anyhow::Error) -> Duration {
+        if let Some(retry_after) = error.downcast_ref::<ApiError>().and_then(|e| e.retry_after) {
+            return retry_after;
+        }
+        let exponential = self
+            .settings
+            .initial_backoff_ms
+            .saturating_mul(1 << (attempt_number - 1).min(16));
+        let capped = exponential.min(self.settings.max_backoff_ms);
+        let jitter: f64 = rand::rng().random_range(0.5..=1.0);
+        Duration::from_millis((capped as f64 * jitter) as u64)
+    }
+
+    async fn complete_once(&self, request: &CompletionRequest) -> Result<Completion> {
+        tokio::time::timeout(self.timeout(), self.inner.complete(request))
+            .await
+            .with_context(|| format!("{} request timed out after {}s", self.inner.name(), self.settings.timeout_secs))?
+    }
+
+    // Forwards deltas from the inner provider, failing when no data arrives within the timeout.
+    // `forwarded` records whether any text reached the caller, after which a retry would duplicate output.
+    async fn stream_once(
+        &self,
+        request: &CompletionRequest,
+        deltas: &mpsc::UnboundedSender<String>,
+        forwarded: &mut bool,
+    ) -> Result<Completion> {
+        let (inner_sender, mut inner_receiver) = mpsc::unbounded_channel();
+        let call = self.inner.stream(request, inner_sender);
+        tokio::pin!(call);
+
+        loop {
+            tokio::select! {
+                result = &mut call => {
+                    while let Ok(delta) = inner_receiver.try_recv() {
+                        *forwarded = true;
+                        let _ = deltas.send(delta);
+                    }
+                    return result;
+                }
+                delta = tokio::time::timeout(self.timeout(), inner_receiver.recv()) => match delta {
+                    Ok(Some(delta)) => {
+                        *forwarded = true;
+                        let _ = deltas.send(delta);
+                    }
+                    // The provider dropped its sender, so the call is about to finish
+                    Ok(None) => return (&mut call).await,
+                    Err(elapsed) => {
+                        return Err(anyhow::Error::from(elapsed).context(format!(
+                            "{} stream stalled for {}s",
+                            self.inner.name(),
+                            self.settings.timeout_secs
+                        )));
+                    }
+                },
+            }
+        }
+    }
+}
+
+// Rate limits, server errors, timeouts and connection failures are worth another attempt
+fn is_retryable(error: &anyhow::Error) -> bool {
+    if let Some(api_error) = error.downcast_ref::<ApiError>() {
+        return api_error.status.as_u16() == 429 || api_error.status.is_server_error();
+    }
+    if error.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+        return true;
+    }
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_timeout() || e.is_connect())
+}
+
+// Short label for the loading screen
+fn short_reason(error: &anyhow::Error) -> String {
+    match error.downcast_ref::<ApiError>() {
+        Some(api_error) if api_error.status.as_u16() == 429 => {
+            format!("{} rate limited the request", api_error.provider)
+        }
+        Some(api_error) => format!("{} returned {}", api_error.provider, api_error.status),
+        None if error.downcast_ref::<tokio::time::error::Elapsed>().is_some() => "Request timed out".to_string(),
+        None => "Connection failed".to_string(),
+    }
+}
+
+impl LlmProvider for RetryingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn complete<'a>(&'a self, request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(self.with_retries(request, move || self.complete_once(request)))
+    }
+
+    fn stream<'a>(
+        &'a self,
+        request: &'a CompletionRequest,
+        deltas: mpsc::UnboundedSender<String>,
+    ) -> BoxFuture<'a, Result<Completion>> {
+        Box::pin(self.with_retries(request, move || {
+            let deltas = deltas.clone();
+            async move {
+                let mut forwarded = false;
+                match self.stream_once(request, &deltas, &mut forwarded).await {
+                    // Partial output has already been shown, so a fresh error ends the retries
+                    Err(error) if forwarded => Err(anyhow::anyhow!("Stream interrupted after output was received: {}", error)),
+                    result => result,
+                }
+            }
+        }))
+    }
+
+    fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+        Box::pin(async move {
+            tokio::time::timeout(self.timeout(), self.inner.list_models())
+                .await
+                .with_context(|| format!("{} model listing timed out", self.inner.name()))?
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm_provider::LlmTask;
+    use reqwest::StatusCode;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    // Fails with `status` until `failures` attempts have been made
+    struct FlakyProvider {
+        status: StatusCode,
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    impl LlmProvider for FlakyProvider {
+        fn name(&self) -> &'static str {
+            "Flaky"
+        }
+
+        fn complete<'a>(&'a self, _request: &'a CompletionRequest) -> BoxFuture<'a, Result<Completion>> {
+            Box::pin(async move {
+                if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                    return Err(ApiError {
+                        provider: "Flaky".to_string(),
+                        status: self.status,
+                        retry_after: None,
+                        body: String::new(),
+                    }
+                    .into());
+                }
+                Ok(Completion::text("done".to_string()))
+            })
+        }
+
+        fn list_models(&self) -> BoxFuture<'_, Result<Vec<Model>>> {
+            Box::pin(async move { Ok(Vec::new()) })
+        }
+    }
+
+    fn retrying(status: StatusCode, failures: u32) -> (Arc<FlakyProvider>, RetryingProvider) {
+        let flaky = Arc::new(FlakyProvider {
+            status,
+            failures,
+            calls: AtomicU32::new(0),
+        });
+        let settings = RetrySettings {
+            timeout_secs: 5,
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 5,
+        };
+        let provider = RetryingProvider::new(flaky.clone(), settings, RetryStatus::default());
+        (flaky, provider)
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limits_until_success() {
+        let (flaky, provider) = retrying(StatusCode::TOO_MANY_REQUESTS, 2);
+        let request = CompletionRequest::new(LlmTask::Questions, "m".to_string(), "p".to_string());
+
+        assert_eq!(provider.complete(&request).await.unwrap().content, "done");
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+        assert!(provider.status.get().is_none());
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_client_errors_and_exhausted_budget() {
+        let request = CompletionRequest::new(LlmTask::Questions, "m".to_string(), "p".to_string());
+
+        let (flaky, provider) = retrying(StatusCode::BAD_REQUEST, 1);
+        assert!(provider.complete(&request).await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 1);
+
+        let (flaky, provider) = retrying(StatusCode::SERVICE_UNAVAILABLE, 5);
+        assert!(provider.complete(&request).await.is_err());
+        assert_eq!(flaky.calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,122 @@
+// src/snippet_runner.rs
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// Progress of a running example, sent line by line as it happens
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunEvent {
+    Stdout(String),
+    // Includes cargo's own "Compiling ..." progress
+    Stderr(String),
+    Exited(Option<i32>),
+    TimedOut,
+    Failed(String),
+}
+
+/// Runs `cargo run --example <example>` in `project_dir`, killing it after `timeout`.
+/// The process is also killed when the future is dropped, e.g. because its task was aborted.
+pub async fn run_example(project_dir: &Path, example: &str, timeout: Duration, events: mpsc::UnboundedSender<RunEvent>) {
+    let spawned = Command::new("cargo")
+        .current_dir(project_dir)
+        .args(["run", "--offline", "--quiet", "--example", example])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = events.send(RunEvent::Failed(format!("Failed to start cargo: {}", err)));
+            return;
+        }
+    };
+
+    let stdout = child.stdout.take().map(|out| tokio::spawn(forward_lines(out, events.clone(), RunEvent::Stdout)));
+    let stderr = child.stderr.take().map(|err| tokio::spawn(forward_lines(err, events.clone(), RunEvent::Stderr)));
+
+    let status = tokio::time::timeout(timeout, child.wait()).await;
+    // On Unix `cargo run` execs the example, so this stops the example itself too
+    if status.is_err() {
+        let _ = child.kill().await;
+    }
+    // Let the readers deliver everything written before the exit
+    for reader in [stdout, stderr].into_iter().flatten() {
+        let _ = reader.await;
+    }
+
+    let _ = events.send(match status {
+        Ok(Ok(status)) => RunEvent::Exited(status.code()),
+        Ok(Err(err)) => RunEvent::Failed(format!("Failed to wait for cargo: {}", err)),
+        Err(_) => RunEvent::TimedOut,
+    });
+}
+
+async fn forward_lines(
+    output: impl AsyncRead + Unpin,
+    events: mpsc::UnboundedSender<RunEvent>,
+    event: fn(String) -> RunEvent,
+) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if events.send(event(line)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::scratch_project;
+
+    async fn run(project_dir: &Path, example: &str, timeout: Duration) -> Vec<RunEvent> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        run_example(project_dir, example, timeout, tx).await;
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn streams_output_and_enforces_the_timeout() {
+        let temp = scratch_project(
+            "run_fixture",
+            "",
+            &[
+                ("src/main.rs", "fn main() {}\n"),
+                (
+                    "examples/hello.rs",
+                    "fn main() {\n    println!(\"hello\");\n    eprintln!(\"oops\");\n    std::process::exit(3);\n}\n",
+                ),
+                (
+                    "examples/forever.rs",
+                    "fn main() {\n    loop {\n        std::thread::sleep(std::time::Duration::from_millis(10));\n    }\n}\n",
+                ),
+            ],
+        );
+        let dir = temp.path();
+
+        let events = run(dir, "hello", Duration::from_secs(120)).await;
+        assert!(events.contains(&RunEvent::Stdout("hello".to_string())));
+        assert!(events.contains(&RunEvent::Stderr("oops".to_string())));
+        assert_eq!(events.last(), Some(&RunEvent::Exited(Some(3))));
+
+        // Build first so the short timeout only has to cover the run itself
+        let build = Command::new("cargo")
+            .current_dir(dir)
+            .args(["build", "--offline", "--quiet", "--example", "forever"])
+            .status()
+            .await
+            .unwrap();
+        assert!(build.success());
+        let events = run(dir, "forever", Duration::from_millis(500)).await;
+        assert_eq!(events.last(), Some(&RunEvent::TimedOut));
+    }
+}
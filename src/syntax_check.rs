@@ -0,0 +1,68 @@
+// src/syntax_check.rs
+use crate::app::LearningModule;
+use std::fmt;
+
+/// Where and why a block of generated code failed to parse as Rust
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Checks that `code` parses as a Rust file or, since snippets often leave out `fn main`,
+/// as a sequence of statements. The reported position is the one from the file parse.
+pub fn check(code: &str) -> Result<(), SyntaxError> {
+    let file_error = match syn::parse_file(code) {
+        Ok(_) => return Ok(()),
+        Err(err) => err,
+    };
+    if syn::parse_str::<syn::Block>(&format!("{{\n{}\n}}", code)).is_ok() {
+        return Ok(());
+    }
+
+    let start = file_error.span().start();
+    Err(SyntaxError {
+        line: start.line,
+        column: start.column + 1,
+        message: file_error.to_string(),
+    })
+}
+
+/// Records the syntax error, if any, of every code snippet and exercise in the module
+pub fn validate_module(module: &mut LearningModule) {
+    for snippet in &mut module.code_snippets {
+        snippet.syntax_error = check(&snippet.code).err();
+        if let Some(err) = &snippet.syntax_error {
+            tracing::warn!("Code snippet \"{}\" is not valid Rust: {}", snippet.title, err);
+        }
+    }
+    for exercise in &mut module.exercises {
+        exercise.syntax_error = check(&exercise.starter_code).err();
+        if let Some(err) = &exercise.syntax_error {
+            tracing::warn!("Starter code of exercise \"{}\" is not valid Rust: {}", exercise.name, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_files_and_statement_snippets() {
+        assert!(check("fn main() {\n    println!(\"hi\");\n}\n").is_ok());
+        assert!(check("let v = vec![1, 2, 3];\nlet total: i32 = v.iter().sum();\n").is_ok());
+        assert!(check("// No code examples provided").is_ok());
+
+        let err = check("fn main() {\n    let x = ;\n}\n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.to_string().starts_with("line 2:"));
+    }
+}
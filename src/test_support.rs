@@ -0,0 +1,21 @@
+// src/test_support.rs
+use std::fs;
+use tempfile::TempDir;
+
+/// Scratch Cargo project in a temporary directory: a package named `name` whose manifest ends with
+/// `manifest_tail`, plus `files` at their paths relative to the project. Its own `[workspace]` keeps cargo
+/// from looking for one further up.
+pub fn scratch_project(name: &str, manifest_tail: &str, files: &[(&str, &str)]) -> TempDir {
+    let temp = tempfile::tempdir().unwrap();
+    let manifest = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[workspace]\n{}",
+        name, manifest_tail
+    );
+    fs::write(temp.path().join("Cargo.toml"), manifest).unwrap();
+    for (path, content) in files {
+        let path = temp.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    temp
+}
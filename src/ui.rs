@@ -1,13 +1,17 @@
 // src/ui.rs
-use crate::app::{App, AppState, SettingsSection, LearningGoal};
+use crate::app::{App, AppState, ExerciseCheck, SettingsSection, SnippetRun};
+use crate::grader::GradeReport;
+use crate::snippet_runner::RunEvent;
+use crate::cargo_project;
 use crate::config::{CodeComplexity, ExplanationVerbosity, FocusArea};
+use crate::llm_provider::LlmTask;
+use crate::syntax_check::SyntaxError;
 use lazy_static::lazy_static;
 use ratatui::prelude::*;
 use ratatui::widgets::*;
 use syntect::easy::HighlightLines;
 use syntect::highlighting::{ThemeSet};
 use syntect::parsing::SyntaxSet;
-use textwrap;
 use textwrap::wrap;
 
 // Initialize syntect resources once
@@ -39,6 +43,7 @@ pub fn render(frame: &mut Frame, app: &App) {
         AppState::QuestionAnswering => render_question_answering_view(frame, app, &main_layout),
         AppState::ApplicationGeneration => render_loading_view(frame, app, &main_layout), // Reuse loading view for application generation
         AppState::ApplicationDisplay => render_application_display_view(frame, app, &main_layout),
+        AppState::Watch => render_watch_view(frame, app, &main_layout),
     }
 
     // Render modals over everything else
@@ -79,8 +84,8 @@ pub fn render_question_generation_view(frame: &mut Frame, app: &App, layout: &[R
 
     frame.render_widget(loading, loading_layout[1]);
 
-    // Render footer
-    let status = Paragraph::new("Please wait...")
+    // Render footer, with retry progress when the provider is backing off
+    let status = Paragraph::new(loading_status_text(app))
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::TOP));
     frame.render_widget(status, layout[2]);
@@ -250,6 +255,28 @@ pub fn render_learning_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
                 .fg(Color::LightYellow)
                 .add_modifier(Modifier::BOLD),
         )]));
+        if let Some(model) = &module.model {
+            content_lines.push(Line::from(Span::styled(
+                format!("Generated by {}", model),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        // Parser diagnostics, so it is visible when part of the response was lost
+        if !module.diagnostics.is_empty() {
+            content_lines.push(Line::from(""));
+            content_lines.push(Line::from(Span::styled(
+                format!("Parser reported {} problem(s) with this response:", module.diagnostics.len()),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )));
+            for diagnostic in &module.diagnostics {
+                let color = if diagnostic.is_error() { Color::LightRed } else { Color::Yellow };
+                content_lines.push(Line::from(Span::styled(
+                    format!("  {}", diagnostic),
+                    Style::default().fg(color),
+                )));
+            }
+        }
+        content_lines.append(&mut compile_report_lines(app));
         content_lines.push(Line::from(""));
 
         content_lines.append(&mut add_colors(&mut highlighter, &module.explanation));
@@ -257,16 +284,41 @@ pub fn render_learning_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
         content_lines.push(Line::from(""));
 
         // Add code snippets with syntax highlighting
+        let snippet_paths = cargo_project::code_snippet_paths(&module.code_snippets);
         for (i, snippet) in module.code_snippets.iter().enumerate() {
+            // The selected snippet is the one (x) runs
+            let (marker, title_style) = if i == app.selected_snippet {
+                ("▶ ", Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD))
+            } else {
+                ("", Style::default().add_modifier(Modifier::BOLD))
+            };
             content_lines.push(Line::from(vec![Span::styled(
-                format!("{}: {}", snippet.title, i + 1),
-                Style::default().add_modifier(Modifier::BOLD),
+                format!("{}{}: {}", marker, snippet.title, i + 1),
+                title_style,
             )]));
 
             if !snippet.description.is_empty() {
                 content_lines.push(Line::from(snippet.description.clone()));
             }
 
+            if let Some(error) = &snippet.syntax_error {
+                content_lines.push(syntax_error_line(error));
+            }
+            if let Some(file) = app
+                .compile_report
+                .as_ref()
+                .and_then(|report| report.file(&snippet_paths[i]))
+                && file.failed()
+            {
+                content_lines.push(Line::from(Span::styled(
+                    format!("✗ Does not compile ({})", file.path.display()),
+                    Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD),
+                )));
+                for line in file.errors.iter().flat_map(|error| error.rendered.lines()) {
+                    content_lines.push(Line::from(Span::styled(line.to_string(), Style::default().fg(Color::Red))));
+                }
+            }
+
             // Add the code lines inside a block
             content_lines.push(Line::from(""));
 
@@ -281,6 +333,9 @@ pub fn render_learning_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
             content_lines.push(Line::from(
                 "└────────────────────────────────────────────────────────────────────────────────────┘",
             ));
+            if let Some(run) = app.snippet_run.as_ref().filter(|run| run.snippet_index == i) {
+                content_lines.append(&mut snippet_run_lines(run, app.is_running_snippet()));
+            }
             content_lines.push(Line::from(""));
         }
 
@@ -290,17 +345,70 @@ pub fn render_learning_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
             Style::default().add_modifier(Modifier::BOLD),
         )]));
 
+        let exercise_paths = cargo_project::exercise_paths(&module.exercises);
         for (i, exercise) in module.exercises.iter().enumerate() {
+            // The selected exercise is the one (t) checks
+            let (marker, title_style) = if i == app.selected_exercise {
+                ("▶ ", Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD))
+            } else {
+                ("", Style::default().add_modifier(Modifier::BOLD))
+            };
             content_lines.push(Line::from(vec![Span::styled(
-                format!("Exercise {}: {}", i + 1, exercise.name),
-                Style::default().add_modifier(Modifier::BOLD),
+                format!("{}Exercise {}: {}", marker, i + 1, exercise.name),
+                title_style,
             )]));
 
             if !exercise.description.is_empty() {
                 content_lines.push(Line::from(exercise.description.clone()));
             }
 
-            content_lines.append(&mut add_colors(&mut highlighter, &exercise.code));
+            if !exercise.problem_statement.is_empty() {
+                content_lines.push(Line::from(""));
+                content_lines.append(&mut add_colors(&mut highlighter, &exercise.problem_statement));
+            }
+
+            for (j, hint) in exercise.hints.iter().enumerate() {
+                content_lines.push(Line::from(vec![
+                    Span::styled(format!("Hint {}: ", j + 1), Style::default().fg(Color::LightBlue)),
+                    Span::raw(hint.clone()),
+                ]));
+            }
+
+            if let Some(expected_output) = &exercise.expected_output {
+                content_lines.push(Line::from(Span::styled(
+                    "Expected output:",
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+                for line in expected_output.lines() {
+                    content_lines.push(Line::from(Span::styled(
+                        format!("  {}", line),
+                        Style::default().fg(Color::Gray),
+                    )));
+                }
+            }
+
+            content_lines.push(Line::from(""));
+            if let Some(error) = &exercise.syntax_error {
+                content_lines.push(syntax_error_line(error));
+            }
+            // Starter code that does not compile is often the point of the exercise, so this is only a note
+            if let Some(file) = app
+                .compile_report
+                .as_ref()
+                .and_then(|report| report.file(&exercise_paths[i]))
+                && file.failed()
+            {
+                content_lines.push(Line::from(Span::styled(
+                    format!("Starter code does not compile yet: {}", file.errors[0].message),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            content_lines.append(&mut add_colors(&mut highlighter, &exercise.starter_code));
+
+            if let Some(check) = app.exercise_check.as_ref().filter(|check| check.exercise_index == i) {
+                content_lines.push(Line::from(""));
+                content_lines.append(&mut exercise_check_lines(check, &exercise_paths[i]));
+            }
 
             content_lines.push(Line::from(""));
         }
@@ -399,7 +507,33 @@ pub fn render_learning_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
     }
 
     // Render footer
-    let status = Paragraph::new("(n) New Module | (k/↑, j/↓) Scroll | (?) Help | (q) Quit")
+    let selection_is_broken = app.current_module.as_ref().is_some_and(|module| {
+        module.code_snippets.get(app.selected_snippet).is_some_and(|snippet| snippet.syntax_error.is_some())
+            || module.exercises.get(app.selected_exercise).is_some_and(|exercise| exercise.syntax_error.is_some())
+    });
+    let footer_text = if app.module_streaming {
+        "Receiving module... | (k/↑, j/↓) Scroll | (Esc/c) Stop | (?) Help | (q) Quit".to_string()
+    } else if app.is_running_snippet() {
+        "Running snippet... | (c) Stop | (Tab) Select Snippet | (k/↑, j/↓) Scroll | (?) Help | (q) Quit".to_string()
+    } else if app.is_checking_exercise() {
+        "Checking solution... | (Tab) Select Snippet | (e) Select Exercise | (k/↑, j/↓) Scroll | (?) Help | (q) Quit".to_string()
+    } else if app.is_compile_checking() {
+        "Compiling project... | (n) New Module | (k/↑, j/↓) Scroll | (?) Help | (q) Quit".to_string()
+    } else if app.is_repairing_snippets() && app.compile_fix_round > 0 {
+        format!(
+            "Fixing snippets that do not compile (round {})... | (n) New Module | (k/↑, j/↓) Scroll | (?) Help | (q) Quit",
+            app.compile_fix_round
+        )
+    } else if app.is_repairing_snippets() {
+        "Regenerating... | (n) New Module | (k/↑, j/↓) Scroll | (?) Help | (q) Quit".to_string()
+    } else if selection_is_broken {
+        "(n) New Module | (r) Regenerate snippet | (R) Regenerate exercise | (Tab) Snippet | (x) Run | (e) Exercise | (t) Check | (?) Help | (q) Quit"
+            .to_string()
+    } else {
+        "(n) New Module | (Tab) Snippet | (x) Run | (e) Exercise | (t) Check | (m) Watch | (k/↑, j/↓) Scroll | (?) Help | (q) Quit"
+            .to_string()
+    };
+    let status = Paragraph::new(footer_text)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::TOP));
     frame.render_widget(status, layout[2]);
@@ -500,11 +634,17 @@ pub fn render_loading_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
         ])
         .split(layout[1]);
 
+    // Application generation reuses this view, retry progress in the footer included
+    let message = match app.current_state {
+        AppState::ApplicationGeneration => "<  Generating your application...  >",
+        _ => "<  Generating your learning module...  >",
+    };
+
     // Render loading message with animation
     let loading_text = vec![
         Line::from(""),
         Line::from(vec![Span::styled(
-            "<  Generating your learning module...  >",
+            message,
             Style::default().add_modifier(Modifier::BOLD),
         )]),
         Line::from(""),
@@ -514,13 +654,176 @@ pub fn render_loading_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
 
     frame.render_widget(loading, loading_layout[1]);
 
-    // Render footer
-    let status = Paragraph::new("Please wait...")
+    // Render footer, with retry progress when the provider is backing off
+    let status = Paragraph::new(loading_status_text(app))
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::TOP));
     frame.render_widget(status, layout[2]);
 }
 
+// Per-test results of (t) Check solution
+fn exercise_check_lines(check: &ExerciseCheck, exercise_path: &std::path::Path) -> Vec<Line<'static>> {
+    let Some(report) = &check.report else {
+        return vec![Line::from(Span::styled(
+            "Checking your solution...",
+            Style::default().fg(Color::DarkGray),
+        ))];
+    };
+
+    let mut lines = grade_report_lines(report);
+    if report.passed() {
+        let solution = std::path::Path::new("solutions").join(exercise_path.file_name().unwrap_or_default());
+        lines.push(Line::from(Span::styled(
+            format!("Compare with the reference solution in {}", solution.display()),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines
+}
+
+// Build errors or per-test results of a graded exercise, shared by the learning and watch views
+fn grade_report_lines(report: &GradeReport) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if !report.errors.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "✗ Your solution does not build yet:",
+            Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD),
+        )));
+        for line in report.errors.iter().flat_map(|error| error.lines()) {
+            lines.push(Line::from(Span::styled(line.to_string(), Style::default().fg(Color::Red))));
+        }
+        return lines;
+    }
+
+    let passed = report.tests.iter().filter(|test| test.passed).count();
+    let (summary, color) = if report.passed() {
+        ("✓ All tests pass", Color::LightGreen)
+    } else {
+        ("✗ Some tests fail", Color::LightRed)
+    };
+    lines.push(Line::from(Span::styled(
+        format!("{} ({}/{})", summary, passed, report.tests.len()),
+        Style::default().fg(color).add_modifier(Modifier::BOLD),
+    )));
+    for test in &report.tests {
+        if test.passed {
+            lines.push(Line::from(Span::styled(format!("  ✓ {}", test.name), Style::default().fg(Color::LightGreen))));
+            continue;
+        }
+        lines.push(Line::from(Span::styled(format!("  ✗ {}", test.name), Style::default().fg(Color::LightRed))));
+        for line in test.failure.lines() {
+            lines.push(Line::from(Span::styled(format!("      {}", line), Style::default().fg(Color::Gray))));
+        }
+    }
+    lines
+}
+
+// Output pane of a snippet run; cargo's build progress arrives on stderr and is dimmed
+fn snippet_run_lines(run: &SnippetRun, running: bool) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::from(Span::styled(
+        if running { "┌─ Output (running...) ─" } else { "┌─ Output ─" },
+        Style::default().fg(Color::LightBlue),
+    ))];
+    for event in &run.events {
+        let line = match event {
+            RunEvent::Stdout(text) => Line::from(format!("│ {}", text)),
+            RunEvent::Stderr(text) => Line::from(Span::styled(format!("│ {}", text), Style::default().fg(Color::DarkGray))),
+            RunEvent::Exited(Some(0)) => {
+                Line::from(Span::styled("└─ Exited successfully", Style::default().fg(Color::LightGreen)))
+            }
+            RunEvent::Exited(code) => Line::from(Span::styled(
+                match code {
+                    Some(code) => format!("└─ Exited with status {}", code),
+                    None => "└─ Killed by a signal".to_string(),
+                },
+                Style::default().fg(Color::LightRed),
+            )),
+            RunEvent::TimedOut => Line::from(Span::styled("└─ Timed out", Style::default().fg(Color::LightRed))),
+            RunEvent::Failed(message) => {
+                Line::from(Span::styled(format!("└─ {}", message), Style::default().fg(Color::LightRed)))
+            }
+        };
+        lines.push(line);
+    }
+    lines
+}
+
+// Per-file `cargo check` summary of the module's project
+fn compile_report_lines(app: &App) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if app.is_compile_checking() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Compile check: running cargo check...",
+            Style::default().fg(Color::DarkGray),
+        )));
+        return lines;
+    }
+    let Some(report) = &app.compile_report else {
+        return lines;
+    };
+
+    let passed = report.files.iter().filter(|file| file.passed()).count();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("Compile check: {} of {} files compile", passed, report.files.len()),
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for error in &report.project_errors {
+        lines.push(Line::from(Span::styled(format!("  ✗ {}", error), Style::default().fg(Color::LightRed))));
+    }
+    for name in app.missing_crates() {
+        lines.push(Line::from(Span::styled(
+            format!("  ✗ {} is not in the local crate cache; add it to Cargo.toml and build once online", name),
+            Style::default().fg(Color::LightRed),
+        )));
+    }
+    for file in &report.files {
+        let path = file.path.display();
+        if file.passed() {
+            let warnings = match file.warnings.len() {
+                0 => String::new(),
+                count => format!(" ({} warning(s))", count),
+            };
+            lines.push(Line::from(Span::styled(
+                format!("  ✓ {}{}", path, warnings),
+                Style::default().fg(Color::LightGreen),
+            )));
+            continue;
+        }
+        if !file.failed() {
+            lines.push(Line::from(Span::styled(format!("  ? {}: not checked", path), Style::default().fg(Color::DarkGray))));
+            continue;
+        }
+
+        // Exercises are often broken on purpose, so only examples are flagged as errors
+        let color = if file.is_exercise() { Color::Yellow } else { Color::LightRed };
+        let first = &file.errors[0];
+        let location = first.line.map(|line| format!(":{}", line)).unwrap_or_default();
+        lines.push(Line::from(Span::styled(
+            format!("  ✗ {}{}: {} ({} error(s))", path, location, first.message, file.errors.len()),
+            Style::default().fg(color),
+        )));
+    }
+    lines
+}
+
+// Marker for code that failed to parse with syn
+fn syntax_error_line(error: &SyntaxError) -> Line<'static> {
+    Line::from(Span::styled(
+        format!("✗ Not valid Rust ({})", error),
+        Style::default().fg(Color::LightRed).add_modifier(Modifier::BOLD),
+    ))
+}
+
+// Footer text shared by the loading screens
+fn loading_status_text(app: &App) -> Line<'static> {
+    match app.llm_retry_status() {
+        Some(retry) => Line::from(Span::styled(retry, Style::default().fg(Color::LightRed))),
+        None => Line::from("Please wait... | (Esc/c) Cancel"),
+    }
+}
+
 // Helper function for rendering modals
 fn render_modal(frame: &mut Frame, area: Rect, widget: impl Widget) {
     // Clear the background
@@ -550,22 +853,26 @@ pub fn render_settings_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
         .split(layout[1]);
 
     // Render sections sidebar
-    let sections = vec![
+    let sections = [
         "Learning Resources",
         "Content Customization",
         "Learning Goals",
         "Question Generator",
+        "Model",
+        "Usage",
     ];
 
     let mut section_lines = Vec::new();
     for (i, section) in sections.iter().enumerate() {
-        let is_selected = match (i, &app.settings_section) {
-            (0, SettingsSection::LearningResources) => true,
-            (1, SettingsSection::ContentCustomization) => true,
-            (2, SettingsSection::LearningGoals) => true,
-            (3, SettingsSection::QuestionGenerator) => true,
-            _ => false,
-        };
+        let is_selected = matches!(
+            (i, &app.settings_section),
+            (0, SettingsSection::LearningResources)
+                | (1, SettingsSection::ContentCustomization)
+                | (2, SettingsSection::LearningGoals)
+                | (3, SettingsSection::QuestionGenerator)
+                | (4, SettingsSection::Model)
+                | (5, SettingsSection::Usage)
+        );
 
         let line = if is_selected {
             Line::from(vec![Span::styled(
@@ -596,10 +903,25 @@ pub fn render_settings_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
         SettingsSection::QuestionGenerator => {
             render_question_generator_settings(frame, app, settings_layout[1]);
         }
+        SettingsSection::Model => {
+            render_model_settings(frame, app, settings_layout[1]);
+        }
+        SettingsSection::Usage => {
+            render_usage_settings(frame, app, settings_layout[1]);
+        }
     }
 
     // Render footer
-    let status = Paragraph::new("(Tab) Switch Section | (k/↑, j/↓) Navigate | (Enter/Space) Toggle | (Esc) Back | (?) Help")
+    let footer_text = if app.settings_section == SettingsSection::Model {
+        if app.model_picker.searching {
+            "Type to search | (Backspace) Delete | (↑/↓) Navigate | (Enter/Esc) Done"
+        } else {
+            "(Tab) Switch Section | (↑/↓) Navigate | (/) Search | (f) Free only | (r) Refresh | (Enter) Select | (Esc) Back"
+        }
+    } else {
+        "(Tab) Switch Section | (k/↑, j/↓) Navigate | (Enter/Space) Toggle | (Esc) Back | (?) Help"
+    };
+    let status = Paragraph::new(footer_text)
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::TOP));
     frame.render_widget(status, layout[2]);
@@ -794,6 +1116,141 @@ fn render_question_generator_settings(frame: &mut Frame, app: &App, area: Rect)
         .block(Block::default().borders(Borders::NONE).title("Options"));
     frame.render_widget(options_widget, area);
 }
+
+fn render_model_settings(frame: &mut Frame, app: &App, area: Rect) {
+    let picker = &app.model_picker;
+
+    let mut option_lines = Vec::new();
+    option_lines.push(Line::from(vec![Span::styled(
+        "Model Settings",
+        Style::default().add_modifier(Modifier::BOLD),
+    )]));
+    option_lines.push(Line::from(""));
+    option_lines.push(Line::from(format!("Current model: {}", app.current_model())));
+
+    let search_style = if picker.searching {
+        Style::default().fg(Color::LightYellow)
+    } else {
+        Style::default()
+    };
+    option_lines.push(Line::from(vec![
+        Span::raw("Search: "),
+        Span::styled(
+            format!("{}{}", picker.query, if picker.searching { "_" } else { "" }),
+            search_style,
+        ),
+        Span::raw(format!("   Free only: [{}]", if picker.free_only { "X" } else { " " })),
+    ]));
+    option_lines.push(Line::from(""));
+
+    if picker.loading {
+        option_lines.push(Line::from("Loading models..."));
+    } else if let Some(error) = &picker.error {
+        option_lines.push(Line::from(Span::styled(
+            format!("Failed to load models: {}", error),
+            Style::default().fg(Color::LightRed),
+        )));
+    } else {
+        let models = picker.filtered();
+        if models.is_empty() {
+            option_lines.push(Line::from("No models match."));
+        }
+
+        // Keep the cursor visible by scrolling the list window
+        let visible = (area.height as usize).saturating_sub(option_lines.len() + 2).max(1);
+        let start = picker.cursor.saturating_sub(visible - 1);
+        for (i, model) in models.iter().enumerate().skip(start).take(visible) {
+            let context = model
+                .context_length
+                .map(|tokens| format!("{}k ctx", tokens / 1000))
+                .unwrap_or_default();
+            let price = match model.pricing.as_ref().and_then(|pricing| pricing.per_million()) {
+                _ if model.is_free() => "free".to_string(),
+                Some((prompt, completion)) => format!("${:.2}/${:.2} per 1M", prompt, completion),
+                None => String::new(),
+            };
+            let current = if model.id == app.current_model() { "*" } else { " " };
+            let text = format!("{}{:<50} {:>9} {}", current, model.id, context, price);
+
+            let line = if i == picker.cursor {
+                Line::from(vec![Span::styled(
+                    format!(">{}", text),
+                    Style::default().fg(Color::Black).bg(Color::LightYellow),
+                )])
+            } else {
+                Line::from(vec![Span::raw(format!(" {}", text))])
+            };
+            option_lines.push(line);
+        }
+    }
+
+    let options_widget = Paragraph::new(option_lines)
+        .block(Block::default().borders(Borders::NONE).title("Options"));
+    frame.render_widget(options_widget, area);
+}
+
+fn render_usage_settings(frame: &mut Frame, app: &App, area: Rect) {
+    let usage = app.usage();
+
+    let mut option_lines = Vec::new();
+    option_lines.push(Line::from(vec![Span::styled(
+        "Token Usage",
+        Style::default().add_modifier(Modifier::BOLD),
+    )]));
+    option_lines.push(Line::from(""));
+
+    for (title, totals) in [("This session", &usage.session), ("Lifetime", &usage.lifetime)] {
+        option_lines.push(Line::from(vec![Span::styled(
+            title,
+            Style::default().fg(Color::LightYellow),
+        )]));
+        option_lines.push(Line::from(format!(
+            "  {:<12} {:>6} {:>12} {:>12} {:>10}",
+            "Feature", "Calls", "Prompt", "Completion", "Cost"
+        )));
+        let rows = [
+            ("Modules", totals.get(LlmTask::Module)),
+            ("Questions", totals.get(LlmTask::Questions)),
+            ("Application", totals.get(LlmTask::Application)),
+            ("Snippets", totals.get(LlmTask::Snippet)),
+            ("Total", &totals.total()),
+        ];
+        for (label, row) in rows {
+            option_lines.push(Line::from(format!(
+                "  {:<12} {:>6} {:>12} {:>12} {:>10}",
+                label,
+                row.calls,
+                row.prompt_tokens,
+                row.completion_tokens,
+                format!("${:.4}", row.cost)
+            )));
+        }
+        option_lines.push(Line::from(""));
+    }
+
+    match usage.budget {
+        Some(budget) => {
+            let spent = usage.lifetime.total().cost;
+            let style = if spent >= budget {
+                Style::default().fg(Color::LightRed)
+            } else {
+                Style::default()
+            };
+            option_lines.push(Line::from(Span::styled(
+                format!("Budget: ${:.2} (${:.2} remaining)", budget, (budget - spent).max(0.0)),
+                style,
+            )));
+        }
+        None => option_lines.push(Line::from("Budget: none (set spend_budget_usd in ~/rust-mentor.conf)")),
+    }
+    option_lines.push(Line::from(""));
+    option_lines.push(Line::from("Costs are only counted when the provider reports them (OpenRouter)."));
+
+    let options_widget = Paragraph::new(option_lines)
+        .block(Block::default().borders(Borders::NONE).title("Options"));
+    frame.render_widget(options_widget, area);
+}
+
 fn render_learning_goals_settings(frame: &mut Frame, app: &App, area: Rect) {
         let learning_goal = app.get_learning_goal();
         let goal_text = learning_goal.to_string();
@@ -849,12 +1306,23 @@ fn render_learning_goals_settings(frame: &mut Frame, app: &App, area: Rect) {
             Line::from("Learning Screen:"),
             Line::from("  k/↑, j/↓ - Scroll content"),
             Line::from("  n - Request new module"),
-            Line::from("  Esc - Return to welcome screen"),
+            Line::from("  r - Regenerate the selected snippet if it is marked as not valid Rust"),
+            Line::from("  R - Regenerate the starter code of the selected exercise if it is not valid Rust"),
+            Line::from("  Tab/Shift+Tab - Select code snippet"),
+            Line::from("  x - Run selected snippet (c stops it)"),
+            Line::from("  e - Select next exercise"),
+            Line::from("  t - Check your solution of the selected exercise against its tests"),
+            Line::from("  m - Watch mode: check exercises on every save, moving on when they pass"),
+            Line::from("  Esc - Return to welcome screen (stops a module still being received)"),
+            Line::from(""),
+            Line::from("While Generating:"),
+            Line::from("  Esc/c - Cancel the request and go back"),
             Line::from(""),
             Line::from("Settings Screen:"),
             Line::from("  Tab - Switch between sections"),
             Line::from("  k/↑, j/↓ - Navigate options"),
             Line::from("  Enter/Space - Toggle or cycle selected option"),
+            Line::from("  Model section: / search, f free only, r refresh, Enter select"),
             Line::from("  Esc - Return to welcome screen"),
         ];
 
@@ -990,13 +1458,8 @@ pub fn render_question_answering_view(frame: &mut Frame, app: &App, layout: &[Re
 
         // Render progress
         let (answered, total) = question_set.progress();
-        let progress_text = format!("Question {}/{}", question_set.current_question_index + 1, total);
         let progress_bar_width = main_content_layout[2].width as usize - 4;
-        let filled_width = if total > 0 {
-            (progress_bar_width * answered) / total
-        } else {
-            0
-        };
+        let filled_width = (progress_bar_width * answered).checked_div(total).unwrap_or(0);
 
         let progress_bar = format!(
             "[{}{}] {}/{} answered",
@@ -1098,6 +1561,84 @@ pub fn render_question_answering_view(frame: &mut Frame, app: &App, layout: &[Re
     frame.render_widget(status, layout[2]);
 }
 
+// Render watch mode: the exercises of the project with the grade of the current one
+pub fn render_watch_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
+    let title = Paragraph::new(format!("Rust AI Mentor :: Watch Mode :: Level {}", app.selected_level))
+        .style(
+            Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::BOTTOM));
+    frame.render_widget(title, layout[0]);
+
+    let Some(session) = &app.watch_session else {
+        return;
+    };
+    let mut content_lines = vec![
+        Line::from(Span::styled(
+            format!("Exercises in {}", session.project_dir.display()),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    // Progress through all exercises
+    for (i, exercise) in session.exercises.iter().enumerate() {
+        let (status, color) = match &exercise.report {
+            Some(report) if report.passed() => ("✓", Color::LightGreen),
+            Some(_) => ("✗", Color::LightRed),
+            None => ("·", Color::DarkGray),
+        };
+        let marker = if i == session.current { "▶" } else { " " };
+        let mut style = Style::default().fg(color);
+        if i == session.current {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        content_lines.push(Line::from(Span::styled(
+            format!("{} {} {}", marker, status, exercise.path.display()),
+            style,
+        )));
+    }
+    content_lines.push(Line::from(""));
+
+    if session.is_done() {
+        content_lines.push(Line::from(Span::styled(
+            "All exercises pass. Well done!",
+            Style::default().fg(Color::LightGreen).add_modifier(Modifier::BOLD),
+        )));
+    } else {
+        if let Some(passed) = session.just_passed {
+            content_lines.push(Line::from(Span::styled(
+                format!("✓ {} passes, moved on to the next exercise", session.exercises[passed].path.display()),
+                Style::default().fg(Color::LightGreen),
+            )));
+        }
+        let current = session.current();
+        content_lines.push(Line::from(vec![
+            Span::raw("Edit "),
+            Span::styled(current.path.display().to_string(), Style::default().fg(Color::LightCyan)),
+            Span::raw(" and save; it is checked again automatically."),
+        ]));
+        content_lines.push(Line::from(""));
+        if app.is_watch_checking() {
+            content_lines.push(Line::from(Span::styled("Checking...", Style::default().fg(Color::DarkGray))));
+        } else if let Some(report) = &current.report {
+            content_lines.append(&mut grade_report_lines(report));
+        }
+    }
+
+    let content = Paragraph::new(content_lines)
+        .block(Block::default().borders(Borders::NONE))
+        .scroll((app.watch_scroll, 0));
+    frame.render_widget(content, layout[1]);
+
+    let status = Paragraph::new("(h/←, l/→) Previous/Next Exercise | (r) Check Again | (k/↑, j/↓) Scroll | (Esc) Back")
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::TOP));
+    frame.render_widget(status, layout[2]);
+}
+
 // Render the application display view
 pub fn render_application_display_view(frame: &mut Frame, app: &App, layout: &[Rect]) {
     // Render title bar
@@ -1153,15 +1694,19 @@ pub fn render_application_display_view(frame: &mut Frame, app: &App, layout: &[R
             .block(Block::default().borders(Borders::ALL).title("Features"));
         frame.render_widget(features, main_content_layout[4]);
 
-        // Render code snippets (just the first one for now)
-        if let Some(first_snippet) = application.code_snippets.first() {
+        // Render the main file, or the first code snippet of the older format (just one for now)
+        let shown = match application.main_file() {
+            Some(file) => Some((format!("{} ({} files)", file.path, application.files.len()), &file.content)),
+            None => application.code_snippets.first().map(|snippet| (snippet.title.clone(), &snippet.code)),
+        };
+        if let Some((title, source)) = shown {
             let syntax = SYNTAX_SET.find_syntax_by_extension("rs").unwrap_or_else(|| SYNTAX_SET.find_syntax_plain_text());
             let mut highlighter = HighlightLines::new(syntax, &THEME_SET.themes["base16-ocean.dark"]);
 
-            let highlighted_code = add_colors(&mut highlighter, &first_snippet.code);
+            let highlighted_code = add_colors(&mut highlighter, source);
 
             let code = Paragraph::new(highlighted_code)
-                .block(Block::default().borders(Borders::ALL).title(format!("Code: {}", first_snippet.title)));
+                .block(Block::default().borders(Borders::ALL).title(format!("Code: {}", title)));
             frame.render_widget(code, main_content_layout[6]);
         }
     } else {
@@ -0,0 +1,251 @@
+// src/usage.rs
+use crate::llm_provider::{LlmTask, TokenUsage};
+use anyhow::Result;
+use chrono::Local;
+use directories::UserDirs;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// One billed LLM call, stored as a line of JSON in the ledger file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UsageRecord {
+    pub timestamp: String,
+    pub task: LlmTask,
+    pub model: String,
+    #[serde(flatten)]
+    pub usage: TokenUsage,
+}
+
+/// Accumulated usage for a group of calls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Sum of the costs reported by the provider; calls without a reported cost add nothing
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: &TokenUsage) {
+        self.calls += 1;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        self.cost += usage.cost.unwrap_or(0.0);
+    }
+
+    fn merge(&mut self, other: &UsageTotals) {
+        self.calls += other.calls;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage split by the feature that made the calls
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TaskTotals {
+    pub module: UsageTotals,
+    pub questions: UsageTotals,
+    pub application: UsageTotals,
+    pub snippet: UsageTotals,
+}
+
+impl TaskTotals {
+    pub fn get(&self, task: LlmTask) -> &UsageTotals {
+        match task {
+            LlmTask::Module => &self.module,
+            LlmTask::Questions => &self.questions,
+            LlmTask::Application => &self.application,
+            LlmTask::Snippet => &self.snippet,
+        }
+    }
+
+    fn get_mut(&mut self, task: LlmTask) -> &mut UsageTotals {
+        match task {
+            LlmTask::Module => &mut self.module,
+            LlmTask::Questions => &mut self.questions,
+            LlmTask::Application => &mut self.application,
+            LlmTask::Snippet => &mut self.snippet,
+        }
+    }
+
+    pub fn total(&self) -> UsageTotals {
+        let mut total = UsageTotals::default();
+        for totals in [&self.module, &self.questions, &self.application, &self.snippet] {
+            total.merge(totals);
+        }
+        total
+    }
+}
+
+/// Point-in-time view of the ledger for the UI
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UsageSnapshot {
+    pub session: TaskTotals,
+    pub lifetime: TaskTotals,
+    pub budget: Option<f64>,
+}
+
+#[derive(Default)]
+struct LedgerState {
+    session: TaskTotals,
+    lifetime: TaskTotals,
+    // Shared with every clone so a budget changed in the settings applies to calls already in flight
+    budget: Option<f64>,
+}
+
+/// Running record of token usage and spend, persisted to `~/rust-mentor-usage.jsonl`
+#[derive(Clone, Default)]
+pub struct UsageLedger {
+    path: Option<PathBuf>,
+    state: Arc<Mutex<LedgerState>>,
+}
+
+impl UsageLedger {
+    /// Ledger kept only in memory, e.g. for tests
+    pub fn in_memory(budget: Option<f64>) -> Self {
+        let ledger = Self::default();
+        ledger.set_budget(budget);
+        ledger
+    }
+
+    /// Opens the ledger in the home directory, next to the config file
+    pub fn open(budget: Option<f64>) -> Self {
+        let user_dirs = UserDirs::new().expect("Could not find user directories");
+        Self::load(user_dirs.home_dir().join("rust-mentor-usage.jsonl"), budget)
+    }
+
+    /// Loads lifetime totals from `path`; unreadable lines are skipped
+    pub fn load(path: PathBuf, budget: Option<f64>) -> Self {
+        let mut lifetime = TaskTotals::default();
+        for record in read_records(&path) {
+            lifetime.get_mut(record.task).add(&record.usage);
+        }
+
+        Self {
+            path: Some(path),
+            state: Arc::new(Mutex::new(LedgerState {
+                session: TaskTotals::default(),
+                lifetime,
+                budget,
+            })),
+        }
+    }
+
+    /// Replaces the spend budget, e.g. after `spend_budget_usd` was changed at runtime
+    pub fn set_budget(&self, budget: Option<f64>) {
+        self.state.lock().unwrap().budget = budget;
+    }
+
+    /// Fails once lifetime spend has reached the configured budget
+    pub fn check_budget(&self) -> Result<()> {
+        let state = self.state.lock().unwrap();
+        if let Some(budget) = state.budget {
+            let spent = state.lifetime.total().cost;
+            if spent >= budget {
+                anyhow::bail!(
+                    "Spend budget of ${:.2} reached (${:.4} spent). Raise spend_budget_usd in ~/rust-mentor.conf to continue.",
+                    budget,
+                    spent
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn record(&self, task: LlmTask, model: &str, usage: TokenUsage) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.session.get_mut(task).add(&usage);
+            state.lifetime.get_mut(task).add(&usage);
+        }
+
+        let record = UsageRecord {
+            timestamp: Local::now().to_rfc3339(),
+            task,
+            model: model.to_string(),
+            usage,
+        };
+        if let Some(path) = &self.path
+            && let Err(err) = append_record(path, &record)
+        {
+            tracing::warn!("Failed to write usage ledger {}: {}", path.display(), err);
+        }
+    }
+
+    pub fn snapshot(&self) -> UsageSnapshot {
+        let state = self.state.lock().unwrap();
+        UsageSnapshot {
+            session: state.session,
+            lifetime: state.lifetime,
+            budget: state.budget,
+        }
+    }
+}
+
+fn read_records(path: &Path) -> Vec<UsageRecord> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(record) => Some(record),
+            Err(err) => {
+                tracing::warn!("Skipping invalid usage record in {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+fn append_record(path: &Path, record: &UsageRecord) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(prompt_tokens: u64, completion_tokens: u64, cost: Option<f64>) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            cost,
+        }
+    }
+
+    #[test]
+    fn persists_lifetime_totals_and_enforces_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("usage.jsonl");
+
+        let ledger = UsageLedger::load(path.clone(), Some(0.01));
+        ledger.record(LlmTask::Module, "a", usage(1000, 2000, Some(0.004)));
+        ledger.record(LlmTask::Questions, "b", usage(100, 50, None));
+        assert!(ledger.check_budget().is_ok());
+
+        // A new session starts from the persisted lifetime totals
+        let reopened = UsageLedger::load(path.clone(), Some(0.01));
+        let snapshot = reopened.snapshot();
+        assert_eq!(snapshot.session.total().calls, 0);
+        assert_eq!(snapshot.lifetime.get(LlmTask::Module).completion_tokens, 2000);
+        assert_eq!(snapshot.lifetime.total().prompt_tokens, 1100);
+
+        reopened.record(LlmTask::Application, "a", usage(10, 10, Some(0.007)));
+        assert_eq!(reopened.snapshot().session.total().calls, 1);
+        assert!(reopened.check_budget().is_err());
+
+        // Raising the budget at runtime applies to every clone of the ledger
+        let clone = reopened.clone();
+        reopened.set_budget(Some(1.0));
+        assert!(clone.check_budget().is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+// src/watch.rs
+use crate::grader::{self, GradeReport};
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// How often the CLI looks for saved changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One exercise binary of a generated project
+pub struct WatchedExercise {
+    // Binary name, as passed to `cargo --bin`
+    pub name: String,
+    // Relative to the project directory, e.g. `src/bin/fix_the_move.rs`
+    pub path: PathBuf,
+    pub report: Option<GradeReport>,
+}
+
+impl WatchedExercise {
+    pub fn passed(&self) -> bool {
+        self.report.as_ref().is_some_and(GradeReport::passed)
+    }
+}
+
+/// Rustlings-style progress through the exercises of a generated project: the current exercise is
+/// graded whenever it changes on disk, and passing it moves on to the next unfinished one
+pub struct WatchSession {
+    pub project_dir: PathBuf,
+    // In the order of the `[[bin]]` sections, which is the order of the module
+    pub exercises: Vec<WatchedExercise>,
+    pub current: usize,
+    // Exercise that was just passed, for a "moved on" notice until the next change
+    pub just_passed: Option<usize>,
+    // Contents of the current exercise and its tests when they were last graded
+    last_seen: Option<String>,
+}
+
+impl WatchSession {
+    pub fn open(project_dir: &Path) -> Result<Self> {
+        let manifest_path = project_dir.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("{} is not a Cargo project", project_dir.display()))?;
+        let manifest: toml::Table = toml::from_str(&manifest).context("Failed to parse Cargo.toml")?;
+
+        let exercises: Vec<WatchedExercise> = manifest
+            .get("bin")
+            .and_then(toml::Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|bin| {
+                let name = bin.get("name")?.as_str()?.to_string();
+                let path = bin
+                    .get("path")
+                    .and_then(toml::Value::as_str)
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|| Path::new("src/bin").join(format!("{}.rs", name)));
+                Some(WatchedExercise { name, path, report: None })
+            })
+            .collect();
+        if exercises.is_empty() {
+            anyhow::bail!("{} has no exercises to watch", project_dir.display());
+        }
+
+        Ok(Self {
+            project_dir: project_dir.to_path_buf(),
+            exercises,
+            current: 0,
+            just_passed: None,
+            last_seen: None,
+        })
+    }
+
+    pub fn current(&self) -> &WatchedExercise {
+        &self.exercises[self.current]
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.exercises.iter().all(WatchedExercise::passed)
+    }
+
+    /// True when the current exercise was never graded or changed on disk since; marks it as seen
+    pub fn needs_check(&mut self) -> bool {
+        let snapshot = self.snapshot();
+        if self.last_seen.as_ref() == Some(&snapshot) {
+            return false;
+        }
+        self.last_seen = Some(snapshot);
+        true
+    }
+
+    /// Switch to another exercise; it is graded on the next `needs_check`
+    pub fn select(&mut self, index: usize) {
+        if index < self.exercises.len() {
+            self.current = index;
+            self.last_seen = None;
+            self.just_passed = None;
+        }
+    }
+
+    /// Grade the current exercise again even though it did not change
+    pub fn recheck(&mut self) {
+        self.last_seen = None;
+    }
+
+    /// Stores the report of the current exercise; returns true when it passed and the session moved on
+    pub fn record(&mut self, report: GradeReport) -> bool {
+        let passed = report.passed();
+        self.exercises[self.current].report = Some(report);
+        self.just_passed = None;
+        if !passed {
+            return false;
+        }
+
+        // The next unfinished exercise, wrapping around to ones skipped earlier
+        let count = self.exercises.len();
+        let next = (1..count)
+            .map(|offset| (self.current + offset) % count)
+            .find(|&index| !self.exercises[index].passed());
+        match next {
+            Some(index) => {
+                let passed_index = self.current;
+                self.select(index);
+                self.just_passed = Some(passed_index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Grade the current exercise with its hidden tests, or by running it when it has none
+    pub fn grade_current(&self, timeout: Duration) -> impl Future<Output = GradeReport> + use<> {
+        let project_dir = self.project_dir.clone();
+        let name = self.current().name.clone();
+        let path = self.current().path.clone();
+        async move {
+            grader::grade_exercise(&project_dir, &name, &path, timeout).await.unwrap_or_else(|err| GradeReport {
+                tests: Vec::new(),
+                errors: vec![err.to_string()],
+            })
+        }
+    }
+
+    // Whatever the grade depends on that the learner may edit
+    fn snapshot(&self) -> String {
+        let exercise = self.current();
+        let tests = self.project_dir.join("grading").join(format!("{}.rs", exercise.name));
+        let mut snapshot = fs::read_to_string(self.project_dir.join(&exercise.path)).unwrap_or_default();
+        snapshot.push('\0');
+        snapshot.push_str(&fs::read_to_string(tests).unwrap_or_default());
+        snapshot
+    }
+}
+
+/// `RustMentor watch [DIR]`: grade the exercises of a generated project on every save, in the terminal
+pub async fn run_cli(project_dir: &Path, timeout: Duration) -> Result<()> {
+    let mut session = WatchSession::open(project_dir)?;
+    println!(
+        "Watching {} exercise(s) in {}. Save an exercise file to check it again; Ctrl-C stops.",
+        session.exercises.len(),
+        project_dir.display()
+    );
+
+    while !session.is_done() {
+        if !session.needs_check() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        let exercise = session.current();
+        println!(
+            "\n==> Checking {} ({}/{})",
+            exercise.path.display(),
+            session.current + 1,
+            session.exercises.len()
+        );
+        let report = session.grade_current(timeout).await;
+        for error in &report.errors {
+            println!("{}", error.trim_end());
+        }
+        for test in &report.tests {
+            println!("  {} {}", if test.passed { "✓" } else { "✗" }, test.name);
+            for line in test.failure.lines() {
+                println!("      {}", line);
+            }
+        }
+
+        let path = session.current().path.clone();
+        if session.record(report) {
+            println!("✓ {} passes. Next up: {}", path.display(), session.current().path.display());
+        } else if !session.current().passed() {
+            println!("Edit {} and save to check again.", path.display());
+        }
+    }
+
+    println!("\nAll exercises pass. Well done!");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grader::TestOutcome;
+    use crate::test_support::scratch_project;
+
+    fn report(passed: bool) -> GradeReport {
+        GradeReport {
+            tests: vec![TestOutcome {
+                name: "works".to_string(),
+                passed,
+                failure: String::new(),
+            }],
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn follows_bin_order_and_moves_on_after_a_pass() {
+        let temp = scratch_project(
+            "watch_fixture",
+            "\n[[bin]]\nname = \"zeta\"\npath = \"src/bin/zeta.rs\"\n\n\
+             [[bin]]\nname = \"alpha\"\npath = \"src/bin/alpha.rs\"\n",
+            &[("src/bin/zeta.rs", "fn main() { todo!() }\n"), ("src/bin/alpha.rs", "fn main() { todo!() }\n")],
+        );
+        let dir = temp.path();
+
+        let mut session = WatchSession::open(dir).unwrap();
+        let names: Vec<&str> = session.exercises.iter().map(|exercise| exercise.name.as_str()).collect();
+        assert_eq!(names, ["zeta", "alpha"]);
+
+        // Graded once, then again only after a save
+        assert!(session.needs_check());
+        assert!(!session.needs_check());
+        assert!(!session.record(report(false)));
+        fs::write(dir.join("src/bin/zeta.rs"), "fn main() {}\n").unwrap();
+        assert!(session.needs_check());
+
+        assert!(session.record(report(true)));
+        assert_eq!(session.current().name, "alpha");
+        assert_eq!(session.just_passed, Some(0));
+        assert!(session.needs_check());
+        assert!(!session.record(report(true)));
+        assert!(session.is_done());
+    }
+}